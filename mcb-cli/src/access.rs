@@ -0,0 +1,65 @@
+use crate::dictionary::{parse_int, Register};
+
+use mcb::mcb_main::Main;
+use mcb::{Config, DataType, PhysicalInterface};
+
+/// Reads a register and formats its value
+pub fn read<INTF: PhysicalInterface>(
+    main: &mut Main<Config, INTF>,
+    register: &Register,
+) -> Result<String, String> {
+    let Register {
+        subnode, address, ..
+    } = *register;
+
+    let value = match register.dtype {
+        DataType::U8 => main.read_u8(subnode, address).map(|v| v.to_string()),
+        DataType::I8 => main.read_i8(subnode, address).map(|v| v.to_string()),
+        DataType::U16 => main.read_u16(subnode, address).map(|v| v.to_string()),
+        DataType::I16 => main.read_i16(subnode, address).map(|v| v.to_string()),
+        DataType::U32 => main.read_u32(subnode, address).map(|v| v.to_string()),
+        DataType::I32 => main.read_i32(subnode, address).map(|v| v.to_string()),
+        DataType::U64 => main.read_u64(subnode, address).map(|v| v.to_string()),
+        DataType::I64 => main.read_i64(subnode, address).map(|v| v.to_string()),
+        DataType::F32 => main.read_f32(subnode, address).map(|v| v.to_string()),
+        DataType::F64 => main.read_f64(subnode, address).map(|v| v.to_string()),
+        DataType::Str => main.read_str(subnode, address),
+    };
+
+    value.map_err(|e| format!("cannot read 0x{:03X}: {:?}", address, e))
+}
+
+/// Parses the value according to the type of the register and writes it
+pub fn write<INTF: PhysicalInterface>(
+    main: &mut Main<Config, INTF>,
+    register: &Register,
+    value: &str,
+) -> Result<(), String> {
+    let Register {
+        subnode, address, ..
+    } = *register;
+    let invalid = || format!("invalid {:?} value `{}`", register.dtype, value);
+
+    let result = match register.dtype {
+        DataType::U8 => main.write_u8(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::I8 => main.write_i8(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::U16 => main.write_u16(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::I16 => main.write_i16(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::U32 => main.write_u32(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::I32 => main.write_i32(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::U64 => main.write_u64(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::I64 => main.write_i64(subnode, address, int(value).ok_or_else(invalid)?),
+        DataType::F32 => main.write_f32(subnode, address, value.parse().map_err(|_| invalid())?),
+        DataType::F64 => main.write_f64(subnode, address, value.parse().map_err(|_| invalid())?),
+        DataType::Str => main.write_str(subnode, address, value),
+    };
+
+    result
+        .map(|_| ())
+        .map_err(|e| format!("cannot write 0x{:03X}: {:?}", address, e))
+}
+
+/// Parses an integer that fits in the type of the register
+fn int<T: TryFrom<i128>>(value: &str) -> Option<T> {
+    parse_int(value)?.try_into().ok()
+}
@@ -0,0 +1,153 @@
+use mcb::parameters::{Access, RegisterDescription};
+use mcb::DataType;
+
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Register of the drive
+#[derive(Debug, Clone, Copy)]
+pub struct Register {
+    pub subnode: u8,
+    pub address: u16,
+    pub dtype: DataType,
+}
+
+/// Symbolic names of the registers. The file holds one register per line:
+/// `<name> <subnode> <address> <type> [ro|wo|rw] [after=<name>[,<name>]...]`.
+/// Registers are read-write unless stated otherwise, and `after` lists the
+/// registers to restore first. Empty lines and lines starting with `#` are
+/// ignored
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    registers: BTreeMap<String, Register>,
+    descriptions: Vec<RegisterDescription>,
+}
+
+impl Dictionary {
+    pub fn load(path: &str) -> Result<Dictionary, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("cannot read `{}`: {}", path, e))?;
+        Dictionary::parse(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(content: &str) -> Result<Dictionary, String> {
+        let mut dictionary = Dictionary::default();
+
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let description = match fields[..] {
+                [name, subnode, address, dtype, ref extra @ ..] => {
+                    parse_register(subnode, address, dtype)
+                        .and_then(|register| parse_description(name, register, extra))
+                }
+                _ => Err("expected `<name> <subnode> <address> <type>`".to_string()),
+            };
+
+            match description {
+                Ok(description) => dictionary.insert(description),
+                Err(e) => return Err(format!("line {}: {}", number + 1, e)),
+            }
+        }
+
+        Ok(dictionary)
+    }
+
+    fn insert(&mut self, description: RegisterDescription) {
+        self.registers.insert(
+            description.name.clone(),
+            Register {
+                subnode: description.subnode,
+                address: description.address,
+                dtype: description.dtype,
+            },
+        );
+        self.descriptions
+            .retain(|other| other.name != description.name);
+        self.descriptions.push(description);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Register> {
+        self.registers.get(name).copied()
+    }
+
+    /// Registers sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Register)> {
+        self.registers
+            .iter()
+            .map(|(name, register)| (name.as_str(), register))
+    }
+
+    /// Registers in file order, as used to save and restore parameters
+    pub fn descriptions(&self) -> &[RegisterDescription] {
+        &self.descriptions
+    }
+}
+
+fn parse_description(
+    name: &str,
+    register: Register,
+    extra: &[&str],
+) -> Result<RegisterDescription, String> {
+    let mut description = RegisterDescription::new(
+        name,
+        register.subnode,
+        register.address,
+        register.dtype,
+        Access::ReadWrite,
+    );
+
+    for field in extra {
+        match *field {
+            "ro" => description.access = Access::ReadOnly,
+            "wo" => description.access = Access::WriteOnly,
+            "rw" => description.access = Access::ReadWrite,
+            _ => match field.strip_prefix("after=") {
+                Some(names) => description
+                    .depends_on
+                    .extend(names.split(',').map(str::to_string)),
+                None => return Err(format!("invalid field `{}`", field)),
+            },
+        }
+    }
+
+    Ok(description)
+}
+
+pub fn parse_register(subnode: &str, address: &str, dtype: &str) -> Result<Register, String> {
+    Ok(Register {
+        subnode: parse_int(subnode)
+            .and_then(|value| u8::try_from(value).ok())
+            .ok_or_else(|| format!("invalid subnode `{}`", subnode))?,
+        address: parse_int(address)
+            .and_then(|value| u16::try_from(value).ok())
+            .ok_or_else(|| format!("invalid address `{}`", address))?,
+        dtype: parse_type(dtype).ok_or_else(|| format!("invalid type `{}`", dtype))?,
+    })
+}
+
+pub fn parse_type(dtype: &str) -> Option<DataType> {
+    DataType::from_name(dtype)
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal integer
+pub fn parse_int(value: &str) -> Option<i128> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+
+    let magnitude = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+
+    Some(if negative { -magnitude } else { magnitude })
+}
@@ -0,0 +1,189 @@
+//! # mcb-cli
+//!
+//! Command line tool to access the registers of a drive through the Motion
+//! Control Bus, over UDP, TCP, serial or Linux spidev.
+
+#![forbid(unsafe_code)]
+
+mod access;
+mod dictionary;
+mod params;
+mod shell;
+mod transport;
+
+use dictionary::{parse_int, parse_register, Dictionary, Register};
+use shell::Session;
+use transport::Transport;
+
+use mcb::mcb_main::{create_main_mcb, Main};
+use mcb::{Config, DataType, ExtMode};
+
+use std::env;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mcb-cli [options] <transport> <command> [arguments]
+
+Transports:
+  udp:<host>:<port>
+  tcp:<host>:<port>
+  serial:<path>[@<baudrate>]
+  spi:<path>[@<speed in Hz>]
+
+Commands:
+  read <name>                              read a register of the dictionary
+  read <subnode> <address> <type>          read a register
+  write <name> <value>                     write a register of the dictionary
+  write <subnode> <address> <type> <value> write a register
+  read-str <name> | <subnode> <address>    read a string register
+  dump <subnode> <first> <last> <type>     read a range of addresses
+  save <file>                              save the dictionary registers
+  restore <file>                           restore and verify a saved file
+  shell                                    start an interactive session
+  script <file>                            run the session commands of a file
+
+Types: u8 i8 u16 i16 u32 i32 u64 i64 f32 f64 str
+
+Parameter files are JSON if their extension is .json and TOML otherwise.
+
+Options:
+  --dict <file>                   dictionary with the names of the registers
+  --mode <extended|segmented>     transmission of extended frames [extended]
+  --subnode <subnode>             subnode receiving the state changes [1]";
+
+const DEFAULT_MAIN_SUBNODE: u8 = 1;
+
+struct Options {
+    dictionary: Dictionary,
+    mode: ExtMode,
+    subnode: u8,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (options, args) = parse_options(args)?;
+    let (transport, command) = match args {
+        [transport, command @ ..] if !command.is_empty() => (transport, command),
+        _ => return Err(format!("missing arguments\n\n{}", USAGE)),
+    };
+
+    let interface = Transport::open(transport)?;
+    let mut main = create_main_mcb(Some(interface), options.mode, options.subnode).init();
+    let command: Vec<&str> = command.iter().map(String::as_str).collect();
+
+    match command[..] {
+        ["shell"] => Session::new(main, options.dictionary).interactive(),
+        ["script", path] => {
+            let mut session = Session::new(main, options.dictionary);
+            let result = session.source(path);
+            session.leave_cyclic().and(result)
+        }
+        _ => execute(&mut main, &options.dictionary, &command),
+    }
+}
+
+fn parse_options(mut args: &[String]) -> Result<(Options, &[String]), String> {
+    let mut options = Options {
+        dictionary: Dictionary::default(),
+        mode: ExtMode::Extended,
+        subnode: DEFAULT_MAIN_SUBNODE,
+    };
+
+    while let [option, value, rest @ ..] = args {
+        match option.as_str() {
+            "--dict" => options.dictionary = Dictionary::load(value)?,
+            "--mode" => {
+                options.mode = match value.as_str() {
+                    "extended" => ExtMode::Extended,
+                    "segmented" => ExtMode::Segmented,
+                    _ => return Err(format!("invalid mode `{}`", value)),
+                }
+            }
+            "--subnode" => {
+                options.subnode = parse_int(value)
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or_else(|| format!("invalid subnode `{}`", value))?
+            }
+            _ => break,
+        }
+        args = rest;
+    }
+
+    Ok((options, args))
+}
+
+/// Runs a single command over the given Main and prints its result
+pub fn execute(
+    main: &mut Main<Config, Transport>,
+    dictionary: &Dictionary,
+    command: &[&str],
+) -> Result<(), String> {
+    match command {
+        ["read", register @ ..] => {
+            let register = lookup(dictionary, register, None)?;
+            println!("{}", access::read(main, &register)?);
+        }
+        ["write", register @ .., value] => {
+            let register = lookup(dictionary, register, None)?;
+            access::write(main, &register, value)?;
+        }
+        ["read-str", register @ ..] => {
+            let register = lookup(dictionary, register, Some(DataType::Str))?;
+            println!("{}", access::read(main, &register)?);
+        }
+        ["dump", subnode, first, last, dtype] => {
+            let first = parse_register(subnode, first, dtype)?;
+            let last = parse_register(subnode, last, dtype)?;
+            for address in first.address..=last.address {
+                let register = Register { address, ..first };
+                match access::read(main, &register) {
+                    Ok(value) => println!("0x{:03X}: {}", address, value),
+                    Err(e) => println!("0x{:03X}: {}", address, e),
+                }
+            }
+        }
+        ["save", path] => params::save(main, dictionary, path)?,
+        ["restore", path] => params::restore(main, dictionary, path)?,
+        _ => return Err(format!("invalid command `{}`", command.join(" "))),
+    }
+
+    Ok(())
+}
+
+/// Finds the register given either by its dictionary name or by its
+/// subnode, address and type. `dtype` replaces the type when the command
+/// implies it
+fn lookup(
+    dictionary: &Dictionary,
+    register: &[&str],
+    dtype: Option<DataType>,
+) -> Result<Register, String> {
+    let register = match (register, dtype) {
+        ([name], _) => dictionary
+            .get(name)
+            .ok_or_else(|| format!("unknown register `{}`", name))?,
+        ([subnode, address], Some(_)) => parse_register(subnode, address, "str")?,
+        ([subnode, address, dtype], None) => parse_register(subnode, address, dtype)?,
+        _ => return Err(format!("invalid register `{}`", register.join(" "))),
+    };
+
+    Ok(Register {
+        dtype: dtype.unwrap_or(register.dtype),
+        ..register
+    })
+}
@@ -0,0 +1,94 @@
+use crate::dictionary::Dictionary;
+
+use mcb::mcb_main::Main;
+use mcb::parameters::{ParameterSet, Value};
+use mcb::{Config, PhysicalInterface};
+
+use std::fs;
+use std::path::Path;
+
+/// Whether the file is JSON. Any other extension is read as TOML
+fn is_json(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// Saves the read-write registers of the dictionary to a parameter file
+pub fn save<INTF: PhysicalInterface>(
+    main: &mut Main<Config, INTF>,
+    dictionary: &Dictionary,
+    path: &str,
+) -> Result<(), String> {
+    let set = main
+        .save_parameters(dictionary.descriptions())
+        .map_err(|e| format!("cannot read {}: {:?}", e.name, e.error))?;
+
+    let content = if is_json(path) {
+        set.to_json()
+    } else {
+        set.to_toml()
+    }
+    .map_err(|e| e.to_string())?;
+
+    fs::write(path, content).map_err(|e| format!("cannot write `{}`: {}", path, e))?;
+    println!("saved {} parameters", set.parameters.len());
+    Ok(())
+}
+
+/// Restores a parameter file and reports the registers that differ
+pub fn restore<INTF: PhysicalInterface>(
+    main: &mut Main<Config, INTF>,
+    dictionary: &Dictionary,
+    path: &str,
+) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("cannot read `{}`: {}", path, e))?;
+    let set = if is_json(path) {
+        ParameterSet::from_json(&content)
+    } else {
+        ParameterSet::from_toml(&content)
+    }
+    .map_err(|e| format!("{}: {}", path, e))?;
+
+    let report = main
+        .restore_parameters(dictionary.descriptions(), &set)
+        .map_err(|e| format!("invalid dependency `{}`", e.name))?;
+
+    for name in &report.skipped {
+        println!("{}: not in the dictionary", name);
+    }
+    for failure in &report.failures {
+        println!("{}: {:?}", failure.name, failure.error);
+    }
+    for difference in &report.differences {
+        println!(
+            "{}: wrote {}, read back {}",
+            difference.name,
+            show(&difference.expected),
+            show(&difference.actual)
+        );
+    }
+    println!("restored {} parameters", report.written.len());
+
+    if report.is_clean() {
+        Ok(())
+    } else {
+        Err("parameters differ from the file".to_string())
+    }
+}
+
+fn show(value: &Value) -> String {
+    match value {
+        Value::U8(v) => v.to_string(),
+        Value::I8(v) => v.to_string(),
+        Value::U16(v) => v.to_string(),
+        Value::I16(v) => v.to_string(),
+        Value::U32(v) => v.to_string(),
+        Value::I32(v) => v.to_string(),
+        Value::U64(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::F32(v) => v.to_string(),
+        Value::F64(v) => v.to_string(),
+        Value::Str(v) => format!("{:?}", v),
+    }
+}
@@ -0,0 +1,285 @@
+use crate::dictionary::{parse_int, Dictionary};
+use crate::transport::Transport;
+use crate::{access, execute, lookup};
+
+use mcb::mcb_main::Main;
+use mcb::{Config, Cyclic};
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+const HISTORY_FILE: &str = ".mcb_cli_history";
+const DEFAULT_WATCH_PERIOD_MS: u64 = 500;
+const DEFAULT_WATCH_COUNT: u64 = 10;
+
+const COMMANDS: &[&str] = &[
+    "cycle", "cyclic", "dump", "exit", "help", "list", "read", "read-str", "restore", "save",
+    "source", "watch", "write",
+];
+
+const HELP: &str = "\
+Commands:
+  read <name> | <subnode> <address> <type>
+  write <name> <value> | <subnode> <address> <type> <value>
+  read-str <name> | <subnode> <address>
+  dump <subnode> <first> <last> <type>
+  save <file> | restore <file>    save or restore the dictionary registers
+  watch [-p <period ms>] [-n <count>] <name> | <subnode> <address> <type>
+  cyclic on|off                   move to cyclic or back to config state
+  cycle <subnode> <word>...       exchange a cyclic frame
+  list                            show the registers of the dictionary
+  source <file>                   run the commands of a file
+  help
+  exit";
+
+/// Communication state of the session
+enum State {
+    Config(Main<Config, Transport>),
+    Cyclic(Main<Cyclic, Transport>),
+}
+
+/// Whether the session keeps reading commands
+enum Flow {
+    Continue,
+    Exit,
+}
+
+/// Commissioning session over a single Main. Commands are read from the
+/// terminal or from a script file
+pub struct Session {
+    state: Option<State>,
+    dictionary: Dictionary,
+}
+
+impl Session {
+    pub fn new(main: Main<Config, Transport>, dictionary: Dictionary) -> Session {
+        Session {
+            state: Some(State::Config(main)),
+            dictionary,
+        }
+    }
+
+    /// Reads commands from the terminal until `exit` or end of input
+    pub fn interactive(&mut self) -> Result<(), String> {
+        let mut editor: Editor<ShellHelper, DefaultHistory> =
+            Editor::new().map_err(|e| e.to_string())?;
+        editor.set_helper(Some(ShellHelper {
+            names: self
+                .dictionary
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }));
+
+        let history = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(history) = &history {
+            let _ = editor.load_history(history);
+        }
+
+        loop {
+            let prompt = match self.state {
+                Some(State::Cyclic(_)) => "mcb (cyclic)> ",
+                _ => "mcb> ",
+            };
+
+            let line = match editor.readline(prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.to_string()),
+            };
+            let _ = editor.add_history_entry(line.as_str());
+
+            match self.run_line(&line) {
+                Ok(Flow::Exit) => break,
+                Ok(Flow::Continue) => (),
+                Err(e) => eprintln!("error: {}", e),
+            }
+        }
+
+        if let Some(history) = &history {
+            let _ = editor.save_history(history);
+        }
+
+        self.leave_cyclic()
+    }
+
+    /// Runs the commands of a file, stopping at the first failing one
+    pub fn source(&mut self, path: &str) -> Result<(), String> {
+        let script =
+            fs::read_to_string(path).map_err(|e| format!("cannot read `{}`: {}", path, e))?;
+
+        for (number, line) in script.lines().enumerate() {
+            match self.run_line(line) {
+                Ok(Flow::Continue) => (),
+                Ok(Flow::Exit) => break,
+                Err(e) => return Err(format!("{}:{}: {}", path, number + 1, e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Leaves the cyclic state so the drive is handed back in config state
+    pub fn leave_cyclic(&mut self) -> Result<(), String> {
+        match self.state {
+            Some(State::Cyclic(_)) => self.set_cyclic(false),
+            _ => Ok(()),
+        }
+    }
+
+    fn run_line(&mut self, line: &str) -> Result<Flow, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(Flow::Continue);
+        }
+
+        let command: Vec<&str> = line.split_whitespace().collect();
+        match command[..] {
+            ["exit"] | ["quit"] => return Ok(Flow::Exit),
+            ["help"] => println!("{}", HELP),
+            ["list"] => {
+                for (name, register) in self.dictionary.iter() {
+                    println!(
+                        "{:<24} {:>2} 0x{:03X} {:?}",
+                        name, register.subnode, register.address, register.dtype
+                    );
+                }
+            }
+            ["source", path] => self.source(path)?,
+            ["cyclic", "on"] => self.set_cyclic(true)?,
+            ["cyclic", "off"] => self.set_cyclic(false)?,
+            ["cycle", subnode, ref words @ ..] => {
+                let subnode = parse_int(subnode)
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or_else(|| format!("invalid subnode `{}`", subnode))?;
+                let words = words
+                    .iter()
+                    .map(|word| {
+                        parse_int(word)
+                            .and_then(|value| u16::try_from(value).ok())
+                            .ok_or_else(|| format!("invalid word `{}`", word))
+                    })
+                    .collect::<Result<Vec<u16>, String>>()?;
+
+                let received = match &mut self.state {
+                    Some(State::Cyclic(main)) => main
+                        .cycle(subnode, &words)
+                        .map_err(|e| format!("cannot cycle: {:?}", e))?,
+                    _ => return Err("not in cyclic state, run `cyclic on`".to_string()),
+                };
+                println!("{:04X?}", received);
+            }
+            ["watch", ref args @ ..] => self.watch(args)?,
+            _ => match &mut self.state {
+                Some(State::Config(main)) => execute(main, &self.dictionary, &command)?,
+                _ => return Err("not in config state, run `cyclic off`".to_string()),
+            },
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn set_cyclic(&mut self, on: bool) -> Result<(), String> {
+        let (state, result) = match (self.state.take(), on) {
+            (Some(State::Config(main)), true) => match main.into_cyclic() {
+                Ok(main) => (State::Cyclic(main), Ok(())),
+                Err((main, e)) => (State::Config(main), Err(format!("{:?}", e))),
+            },
+            (Some(State::Cyclic(main)), false) => match main.into_config() {
+                Ok(main) => (State::Config(main), Ok(())),
+                Err((main, e)) => (State::Cyclic(main), Err(format!("{:?}", e))),
+            },
+            (Some(state), _) => (state, Ok(())),
+            (None, _) => return Err("session closed".to_string()),
+        };
+
+        self.state = Some(state);
+        result.map_err(|e| format!("state change rejected: {}", e))
+    }
+
+    fn watch(&mut self, mut args: &[&str]) -> Result<(), String> {
+        let mut period = DEFAULT_WATCH_PERIOD_MS;
+        let mut count = DEFAULT_WATCH_COUNT;
+
+        while let [option @ ("-p" | "-n"), value, rest @ ..] = args {
+            let value = parse_int(value)
+                .and_then(|value| u64::try_from(value).ok())
+                .ok_or_else(|| format!("invalid value `{}`", value))?;
+            match *option {
+                "-p" => period = value,
+                _ => count = value,
+            }
+            args = rest;
+        }
+
+        let register = lookup(&self.dictionary, args, None)?;
+        let main = match &mut self.state {
+            Some(State::Config(main)) => main,
+            _ => return Err("not in config state, run `cyclic off`".to_string()),
+        };
+
+        for index in 0..count {
+            if index > 0 {
+                thread::sleep(Duration::from_millis(period));
+            }
+            println!("{}", access::read(main, &register)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Completes command names and the names of the dictionary
+struct ShellHelper {
+    names: Vec<String>,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let candidates: Vec<String> = match previous[..] {
+            [] => COMMANDS.iter().map(|command| command.to_string()).collect(),
+            ["cyclic"] => vec!["on".to_string(), "off".to_string()],
+            _ => self.names.clone(),
+        };
+
+        Ok((
+            start,
+            candidates
+                .into_iter()
+                .filter(|candidate| candidate.starts_with(word))
+                .collect(),
+        ))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
@@ -0,0 +1,162 @@
+use mcb::{IntfError, IntfResult, PhysicalInterface, MAX_FRAME_SIZE};
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::time::Duration;
+
+/// Time to wait for the answer of the drive
+const TIMEOUT: Duration = Duration::from_secs(1);
+const SERIAL_BAUDRATE: u32 = 115_200;
+#[cfg(target_os = "linux")]
+const SPI_SPEED_HZ: u32 = 1_000_000;
+
+/// Link to the drive. Every variant carries whole frames, as little endian
+/// words:
+///  * UDP: one frame per datagram
+///  * TCP and serial: each frame is preceded by its length in words (u16)
+///  * SPI: 16 bit words, full duplex. Reading clocks out a whole frame
+pub enum Transport {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Serial(Box<dyn serialport::SerialPort>),
+    #[cfg(target_os = "linux")]
+    Spi(spidev::Spidev),
+}
+
+impl Transport {
+    /// Opens a transport from its description:
+    /// `udp:<host>:<port>`, `tcp:<host>:<port>`, `serial:<path>[@<baudrate>]`
+    /// or `spi:<path>[@<speed in Hz>]`
+    pub fn open(description: &str) -> Result<Transport, String> {
+        let (kind, target) = description
+            .split_once(':')
+            .ok_or_else(|| format!("invalid transport `{}`", description))?;
+
+        match kind {
+            "udp" => {
+                let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+                socket.connect(target).map_err(|e| e.to_string())?;
+                socket
+                    .set_read_timeout(Some(TIMEOUT))
+                    .map_err(|e| e.to_string())?;
+                Ok(Transport::Udp(socket))
+            }
+            "tcp" => {
+                let stream = TcpStream::connect(target).map_err(|e| e.to_string())?;
+                stream
+                    .set_read_timeout(Some(TIMEOUT))
+                    .map_err(|e| e.to_string())?;
+                stream.set_nodelay(true).map_err(|e| e.to_string())?;
+                Ok(Transport::Tcp(stream))
+            }
+            "serial" => {
+                let (path, baudrate) = split_speed(target, SERIAL_BAUDRATE)?;
+                let port = serialport::new(path, baudrate)
+                    .timeout(TIMEOUT)
+                    .open()
+                    .map_err(|e| e.to_string())?;
+                Ok(Transport::Serial(port))
+            }
+            #[cfg(target_os = "linux")]
+            "spi" => {
+                use spidev::{SpiModeFlags, Spidev, SpidevOptions};
+
+                let (path, speed) = split_speed(target, SPI_SPEED_HZ)?;
+                let mut spi = Spidev::open(path).map_err(|e| e.to_string())?;
+                let options = SpidevOptions::new()
+                    .bits_per_word(16)
+                    .max_speed_hz(speed)
+                    .mode(SpiModeFlags::SPI_MODE_1)
+                    .build();
+                spi.configure(&options).map_err(|e| e.to_string())?;
+                Ok(Transport::Spi(spi))
+            }
+            _ => Err(format!("unknown transport `{}`", kind)),
+        }
+    }
+}
+
+/// Splits `<path>@<speed>`, using the default speed if none is given
+fn split_speed(target: &str, default: u32) -> Result<(&str, u32), String> {
+    match target.split_once('@') {
+        Some((path, speed)) => speed
+            .parse()
+            .map(|speed| (path, speed))
+            .map_err(|_| format!("invalid speed `{}`", speed)),
+        None => Ok((target, default)),
+    }
+}
+
+fn to_bytes(words: &[u16]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+fn to_frame(bytes: &[u8]) -> Box<[u16; MAX_FRAME_SIZE]> {
+    let mut frame = Box::new([0u16; MAX_FRAME_SIZE]);
+    for (word, pair) in frame.iter_mut().zip(bytes.chunks_exact(2)) {
+        *word = u16::from_le_bytes([pair[0], pair[1]]);
+    }
+    frame
+}
+
+fn write_prefixed<S: Write + ?Sized>(stream: &mut S, frame: &[u16]) -> std::io::Result<()> {
+    let mut bytes = (frame.len() as u16).to_le_bytes().to_vec();
+    bytes.extend(to_bytes(frame));
+    stream.write_all(&bytes)?;
+    stream.flush()
+}
+
+fn read_prefixed<S: Read + ?Sized>(stream: &mut S) -> std::io::Result<Box<[u16; MAX_FRAME_SIZE]>> {
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length)?;
+    let size = u16::from_le_bytes(length) as usize;
+    if size > MAX_FRAME_SIZE {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    let mut bytes = vec![0u8; size * 2];
+    stream.read_exact(&mut bytes)?;
+    Ok(to_frame(&bytes))
+}
+
+impl PhysicalInterface for Transport {
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        let result = match self {
+            Transport::Udp(socket) => socket.send(&to_bytes(frame)).map(|_| ()),
+            Transport::Tcp(stream) => write_prefixed(stream, frame),
+            Transport::Serial(port) => write_prefixed(port.as_mut(), frame),
+            #[cfg(target_os = "linux")]
+            Transport::Spi(spi) => spi.write_all(&to_bytes(frame)),
+        };
+
+        match result {
+            Ok(()) => Ok(IntfResult::Success),
+            Err(_) => Err(IntfError::Interface),
+        }
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        let result = match self {
+            Transport::Udp(socket) => {
+                let mut bytes = [0u8; MAX_FRAME_SIZE * 2];
+                socket.recv(&mut bytes).map(|size| to_frame(&bytes[..size]))
+            }
+            Transport::Tcp(stream) => read_prefixed(stream),
+            Transport::Serial(port) => read_prefixed(port.as_mut()),
+            #[cfg(target_os = "linux")]
+            Transport::Spi(spi) => {
+                use spidev::SpidevTransfer;
+
+                let tx = [0u8; MAX_FRAME_SIZE * 2];
+                let mut rx = [0u8; MAX_FRAME_SIZE * 2];
+                let mut transfer = SpidevTransfer::read_write(&tx, &mut rx);
+                spi.transfer(&mut transfer).map(|_| to_frame(&rx))
+            }
+        };
+
+        match result {
+            Ok(frame) => Ok(IntfResult::Data(frame)),
+            Err(_) => Err(IntfError::Interface),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use mcb::mcb_node::{create_node_mcb, CommandType, Node};
+use mcb::{Config, Cyclic, ExtMode, IntfError, IntfResult, PhysicalInterface, MAX_FRAME_SIZE};
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::{SocketAddr, UdpSocket};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const NODE_SUBNODE: u8 = 2u8;
+const STR_ADDRESS: u16 = 0x020u16;
+const F32_ADDRESS: u16 = 0x030u16;
+
+struct UdpNode {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+}
+
+impl PhysicalInterface for UdpNode {
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        let bytes: Vec<u8> = frame.iter().flat_map(|word| word.to_le_bytes()).collect();
+        match self.peer.map(|peer| self.socket.send_to(&bytes, peer)) {
+            Some(Ok(_)) => Ok(IntfResult::Success),
+            _ => Err(IntfError::Interface),
+        }
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        let mut bytes = [0u8; MAX_FRAME_SIZE * 2];
+        let (size, peer) = match self.socket.recv_from(&mut bytes) {
+            Ok(value) => value,
+            Err(_) => return Err(IntfError::Interface),
+        };
+        self.peer = Some(peer);
+
+        let mut frame = [0u16; MAX_FRAME_SIZE];
+        for (word, pair) in frame.iter_mut().zip(bytes[..size].chunks_exact(2)) {
+            *word = u16::from_le_bytes([pair[0], pair[1]]);
+        }
+        Ok(IntfResult::Data(Box::new(frame)))
+    }
+}
+
+/// Starts a node answering over UDP. Returns its transport description and
+/// the registers it holds
+fn spawn_udp_node() -> (String, Arc<Mutex<HashMap<u16, u16>>>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let transport = format!("udp:{}", socket.local_addr().unwrap());
+    let registers = Arc::new(Mutex::new(HashMap::from([(0x010u16, 0x1234u16)])));
+    let node_registers = registers.clone();
+
+    thread::spawn(move || {
+        let interface = UdpNode { socket, peer: None };
+        let mut node_cfg = create_node_mcb(Some(interface), ExtMode::Extended, NODE_SUBNODE).init();
+        let mut float = 0u32;
+
+        while let Ok(request) = node_cfg.read() {
+            if let CommandType::StateChange(_) = request.command {
+                let Ok(node_cyc) = node_cfg.into_cyclic(&request) else {
+                    return;
+                };
+                let Some(node) = serve_cyclic(node_cyc) else {
+                    return;
+                };
+                node_cfg = node;
+                continue;
+            }
+
+            let mut registers = node_registers.lock().unwrap();
+            let _ = match request.command {
+                CommandType::Read if request.address == STR_ADDRESS => {
+                    node_cfg.write_str(request.address, "drive")
+                }
+                CommandType::Read if request.address == F32_ADDRESS => {
+                    node_cfg.write_u32(request.address, float)
+                }
+                CommandType::Write if request.address == F32_ADDRESS => {
+                    float = node_cfg.get_data_u32(&request);
+                    node_cfg.ack(request.address)
+                }
+                CommandType::Read => match registers.get(&request.address) {
+                    Some(value) => node_cfg.write_u16(request.address, *value),
+                    None => node_cfg.error(0, 0x0601_0000u32),
+                },
+                _ => {
+                    let value = node_cfg.get_data_u16(&request);
+                    registers.insert(request.address, value);
+                    node_cfg.write_u16(request.address, value)
+                }
+            };
+        }
+    });
+
+    (transport, registers)
+}
+
+/// Answers cyclic frames with the received data incremented by one until the
+/// main requests to go back to config state
+fn serve_cyclic(mut node_cyc: Node<Cyclic, UdpNode>) -> Option<Node<Config, UdpNode>> {
+    while let Ok(request) = node_cyc.read() {
+        if let CommandType::StateChange(_) = request.command {
+            return node_cyc.into_config(&request).ok();
+        }
+
+        let data: Vec<u16> = node_cyc
+            .get_cyclic_data(&request, 2)
+            .iter()
+            .map(|word| word + 1)
+            .collect();
+        let _ = node_cyc.write_cyclic(&data);
+    }
+
+    None
+}
+
+fn mcb_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mcb-cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_cli_read_write() {
+    let (transport, registers) = spawn_udp_node();
+
+    let output = mcb_cli(&[&transport, "read", "2", "0x010", "u16"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "4660\n");
+
+    let output = mcb_cli(&[&transport, "write", "2", "0x011", "u16", "0xBEEF"]);
+    assert!(output.status.success());
+    assert_eq!(registers.lock().unwrap().get(&0x011u16), Some(&0xBEEFu16));
+
+    let output = mcb_cli(&[&transport, "read-str", "2", "0x020"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "drive\n");
+}
+
+#[test]
+fn test_cli_float() {
+    let (transport, _) = spawn_udp_node();
+
+    let output = mcb_cli(&[&transport, "write", "2", "0x030", "f32", "1.5"]);
+    assert!(output.status.success());
+
+    let output = mcb_cli(&[&transport, "read", "2", "0x030", "f32"]);
+    assert_eq!(stdout(&output), "1.5\n");
+}
+
+#[test]
+fn test_cli_dictionary_and_dump() {
+    let (transport, _) = spawn_udp_node();
+    let path = std::env::temp_dir().join(format!("mcb-cli-test-{}.dict", std::process::id()));
+    std::fs::write(
+        &path,
+        "# name subnode address type\nSTATUS 2 0x010 u16\nNAME 2 0x020 str\n",
+    )
+    .unwrap();
+    let dict = path.to_str().unwrap();
+
+    let output = mcb_cli(&["--dict", dict, &transport, "read", "STATUS"]);
+    assert_eq!(stdout(&output), "4660\n");
+
+    let output = mcb_cli(&["--dict", dict, &transport, "read", "NAME"]);
+    assert_eq!(stdout(&output), "drive\n");
+
+    let output = mcb_cli(&["--dict", dict, &transport, "read", "UNKNOWN"]);
+    assert!(!output.status.success());
+
+    let output = mcb_cli(&[&transport, "dump", "2", "0x00F", "0x010", "u16"]);
+    let dump = stdout(&output);
+    let lines: Vec<&str> = dump.lines().collect();
+    assert!(lines[0].starts_with("0x00F: cannot read"));
+    assert_eq!(lines[1], "0x010: 4660");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_cli_invalid_arguments() {
+    let (transport, _) = spawn_udp_node();
+
+    assert!(!mcb_cli(&[&transport, "write", "2", "0x011", "u8", "300"])
+        .status
+        .success());
+    assert!(!mcb_cli(&[&transport, "read", "2", "0x010", "u128"])
+        .status
+        .success());
+    assert!(!mcb_cli(&["pipe:0", "read", "2", "0x010", "u16"])
+        .status
+        .success());
+}
+
+#[test]
+fn test_cli_script() {
+    let (transport, registers) = spawn_udp_node();
+    let path = std::env::temp_dir().join(format!("mcb-cli-test-{}.mcb", std::process::id()));
+    std::fs::write(
+        &path,
+        "# commissioning\n\
+         write 2 0x012 u16 7\n\
+         watch -p 0 -n 2 2 0x012 u16\n\
+         cyclic on\n\
+         cycle 2 0x0010 0x0020\n\
+         cyclic off\n\
+         read 2 0x010 u16\n",
+    )
+    .unwrap();
+
+    let output = mcb_cli(&[
+        "--subnode",
+        "2",
+        &transport,
+        "script",
+        path.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "7\n7\n[0011, 0021]\n4660\n");
+    assert_eq!(registers.lock().unwrap().get(&0x012u16), Some(&7u16));
+}
+
+#[test]
+fn test_cli_script_stops_at_error() {
+    let (transport, registers) = spawn_udp_node();
+    let path = std::env::temp_dir().join(format!("mcb-cli-error-{}.mcb", std::process::id()));
+    std::fs::write(&path, "cycle 2 0x0001\nwrite 2 0x013 u16 1\n").unwrap();
+
+    let output = mcb_cli(&[&transport, "script", path.to_str().unwrap()]);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(":1: not in cyclic state"));
+    assert_eq!(registers.lock().unwrap().get(&0x013u16), None);
+}
+
+#[test]
+fn test_cli_shell() {
+    let (transport, _) = spawn_udp_node();
+
+    let mut shell = Command::new(env!("CARGO_BIN_EXE_mcb-cli"))
+        .args(["--subnode", "2", &transport, "shell"])
+        .env("HOME", std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    shell
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"read 2 0x010 u16\ncyclic on\nread 2 0x010 u16\ncyclic off\nread-str 2 0x020\n")
+        .unwrap();
+    let output = shell.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("4660"));
+    assert!(stdout(&output).contains("drive"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not in config state"));
+}
+
+#[test]
+fn test_cli_save_restore() {
+    let (transport, registers) = spawn_udp_node();
+    registers.lock().unwrap().insert(0x011, 40);
+    let base = std::env::temp_dir().join(format!("mcb-cli-test-{}-params", std::process::id()));
+    let dict = base.with_extension("dict");
+    std::fs::write(
+        &dict,
+        "LIMIT 2 0x011 u16 after=STATUS\nSTATUS 2 0x010 u16 rw\nNAME 2 0x020 str ro\n",
+    )
+    .unwrap();
+    let dict = dict.to_str().unwrap();
+
+    for extension in ["json", "toml"] {
+        let file = base.with_extension(extension);
+        let file = file.to_str().unwrap();
+
+        let output = mcb_cli(&["--dict", dict, &transport, "save", file]);
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), "saved 2 parameters\n");
+
+        registers.lock().unwrap().insert(0x010, 1);
+        registers.lock().unwrap().insert(0x011, 2);
+
+        let output = mcb_cli(&["--dict", dict, &transport, "restore", file]);
+        assert!(output.status.success());
+        assert_eq!(stdout(&output), "restored 2 parameters\n");
+        assert_eq!(registers.lock().unwrap().get(&0x010u16), Some(&0x1234u16));
+        assert_eq!(registers.lock().unwrap().get(&0x011u16), Some(&40u16));
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    std::fs::remove_file(dict).unwrap();
+}
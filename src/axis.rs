@@ -0,0 +1,138 @@
+use crate::cia402::Cia402;
+use crate::mcb_main::Main;
+use crate::*;
+
+/// Handle to the registers of a single subnode. See [`Main::axis`]
+pub struct Axis<'a, INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    main: &'a mut Main<Config, INTF, N>,
+    subnode: u8,
+}
+
+impl<INTF, const N: usize> Main<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Gives access to the registers of the given subnode
+    pub fn axis(&mut self, subnode: u8) -> Axis<'_, INTF, N> {
+        Axis {
+            main: self,
+            subnode,
+        }
+    }
+
+    /// Gives access to the registers of the subnode the Main was created for
+    pub fn default_axis(&mut self) -> Axis<'_, INTF, N> {
+        let subnode = self.subnode();
+        self.axis(subnode)
+    }
+}
+
+impl<'a, INTF, const N: usize> Axis<'a, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    pub fn subnode(&self) -> u8 {
+        self.subnode
+    }
+
+    /// Turns the handle into a handle to the state machine of the axis
+    pub fn cia402(self) -> Cia402<'a, INTF, N> {
+        self.main.cia402(self.subnode)
+    }
+
+    pub fn write_u8(&mut self, add: u16, data: u8) -> Result<IntfResult, IntfError> {
+        self.main.write_u8(self.subnode, add, data)
+    }
+
+    pub fn read_u8(&mut self, add: u16) -> Result<u8, IntfError> {
+        self.main.read_u8(self.subnode, add)
+    }
+
+    pub fn write_i8(&mut self, add: u16, data: i8) -> Result<IntfResult, IntfError> {
+        self.main.write_i8(self.subnode, add, data)
+    }
+
+    pub fn read_i8(&mut self, add: u16) -> Result<i8, IntfError> {
+        self.main.read_i8(self.subnode, add)
+    }
+
+    pub fn write_u16(&mut self, add: u16, data: u16) -> Result<IntfResult, IntfError> {
+        self.main.write_u16(self.subnode, add, data)
+    }
+
+    pub fn read_u16(&mut self, add: u16) -> Result<u16, IntfError> {
+        self.main.read_u16(self.subnode, add)
+    }
+
+    pub fn write_i16(&mut self, add: u16, data: i16) -> Result<IntfResult, IntfError> {
+        self.main.write_i16(self.subnode, add, data)
+    }
+
+    pub fn read_i16(&mut self, add: u16) -> Result<i16, IntfError> {
+        self.main.read_i16(self.subnode, add)
+    }
+
+    pub fn write_u32(&mut self, add: u16, data: u32) -> Result<IntfResult, IntfError> {
+        self.main.write_u32(self.subnode, add, data)
+    }
+
+    pub fn read_u32(&mut self, add: u16) -> Result<u32, IntfError> {
+        self.main.read_u32(self.subnode, add)
+    }
+
+    pub fn write_i32(&mut self, add: u16, data: i32) -> Result<IntfResult, IntfError> {
+        self.main.write_i32(self.subnode, add, data)
+    }
+
+    pub fn read_i32(&mut self, add: u16) -> Result<i32, IntfError> {
+        self.main.read_i32(self.subnode, add)
+    }
+
+    pub fn write_u64(&mut self, add: u16, data: u64) -> Result<IntfResult, IntfError> {
+        self.main.write_u64(self.subnode, add, data)
+    }
+
+    pub fn read_u64(&mut self, add: u16) -> Result<u64, IntfError> {
+        self.main.read_u64(self.subnode, add)
+    }
+
+    pub fn write_i64(&mut self, add: u16, data: i64) -> Result<IntfResult, IntfError> {
+        self.main.write_i64(self.subnode, add, data)
+    }
+
+    pub fn read_i64(&mut self, add: u16) -> Result<i64, IntfError> {
+        self.main.read_i64(self.subnode, add)
+    }
+
+    pub fn write_f32(&mut self, add: u16, data: f32) -> Result<IntfResult, IntfError> {
+        self.main.write_f32(self.subnode, add, data)
+    }
+
+    pub fn read_f32(&mut self, add: u16) -> Result<f32, IntfError> {
+        self.main.read_f32(self.subnode, add)
+    }
+
+    pub fn write_f64(&mut self, add: u16, data: f64) -> Result<IntfResult, IntfError> {
+        self.main.write_f64(self.subnode, add, data)
+    }
+
+    pub fn read_f64(&mut self, add: u16) -> Result<f64, IntfError> {
+        self.main.read_f64(self.subnode, add)
+    }
+
+    pub fn write_str(&mut self, add: u16, data: &str) -> Result<IntfResult, IntfError> {
+        self.main.write_str(self.subnode, add, data)
+    }
+
+    pub fn read_str(&mut self, add: u16) -> Result<String, IntfError> {
+        self.main.read_str(self.subnode, add)
+    }
+
+    pub fn write_words(&mut self, add: u16, data: &[u16]) -> Result<IntfResult, IntfError> {
+        self.main.write_words(self.subnode, add, data)
+    }
+
+    pub fn read_words(&mut self, add: u16) -> Result<Vec<u16>, IntfError> {
+        self.main.read_words(self.subnode, add)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::mcb_main::Main;
+use crate::*;
+
+/// Errors of the axes that failed during a bus access. Each entry holds
+/// the index of the axis and its error
+#[derive(Debug)]
+pub struct BusError {
+    pub errors: Vec<(usize, IntfError)>,
+}
+
+/// Axis of a Bus in the state of its drive
+pub enum BusAxis<INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    Config(Main<Config, INTF, N>),
+    Cyclic(Main<Cyclic, INTF, N>),
+}
+
+/// Failed state change of a Bus. The axes are handed back in the state of
+/// their drive, in the order of the Bus: an axis that could not go back after
+/// the failure is reported in the state it reached
+pub struct BusTransitionError<INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    pub axes: Vec<BusAxis<INTF, N>>,
+    pub errors: Vec<(usize, IntfError)>,
+}
+
+impl<INTF, const N: usize> BusTransitionError<INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Builds a Bus in config state back, if every axis is in config state
+    #[allow(clippy::result_large_err)]
+    pub fn into_config_bus(self) -> Result<Bus<Config, INTF, N>, BusTransitionError<INTF, N>> {
+        if !self
+            .axes
+            .iter()
+            .all(|axis| matches!(axis, BusAxis::Config(_)))
+        {
+            return Err(self);
+        }
+
+        let axes = self
+            .axes
+            .into_iter()
+            .filter_map(|axis| match axis {
+                BusAxis::Config(axis) => Some(axis),
+                BusAxis::Cyclic(_) => None,
+            })
+            .collect();
+        Ok(Bus { axes })
+    }
+
+    /// Builds a Bus in cyclic state back, if every axis is in cyclic state
+    #[allow(clippy::result_large_err)]
+    pub fn into_cyclic_bus(self) -> Result<Bus<Cyclic, INTF, N>, BusTransitionError<INTF, N>> {
+        if !self
+            .axes
+            .iter()
+            .all(|axis| matches!(axis, BusAxis::Cyclic(_)))
+        {
+            return Err(self);
+        }
+
+        let axes = self
+            .axes
+            .into_iter()
+            .filter_map(|axis| match axis {
+                BusAxis::Cyclic(axis) => Some(axis),
+                BusAxis::Config(_) => None,
+            })
+            .collect();
+        Ok(Bus { axes })
+    }
+}
+
+/// Set of Main devices, one per axis, driven together
+pub struct Bus<STATE, INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    axes: Vec<Main<STATE, INTF, N>>,
+}
+
+/// These functions may be used on any Bus struct
+impl<STATE, INTF, const N: usize> Bus<STATE, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Number of axes
+    pub fn len(&self) -> usize {
+        self.axes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.axes.is_empty()
+    }
+
+    /// Gives access to a single axis
+    pub fn axis(&mut self, index: usize) -> Option<&mut Main<STATE, INTF, N>> {
+        self.axes.get_mut(index)
+    }
+}
+
+impl<INTF, const N: usize> Bus<Init, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    pub fn init(self) -> Bus<Config, INTF, N> {
+        Bus {
+            axes: self.axes.into_iter().map(|axis| axis.init()).collect(),
+        }
+    }
+}
+
+/// These functions may be used on any Bus in config State
+impl<INTF, const N: usize> Bus<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Runs the same configuration access on every axis. The closure receives
+    /// the index of the axis, so per axis values can be written too
+    pub fn broadcast<F>(&mut self, mut access: F) -> Result<IntfResult, BusError>
+    where
+        F: FnMut(usize, &mut Main<Config, INTF, N>) -> Result<IntfResult, IntfError>,
+    {
+        let errors: Vec<(usize, IntfError)> = self
+            .axes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, axis)| access(index, axis).err().map(|e| (index, e)))
+            .collect();
+
+        if errors.is_empty() {
+            Ok(IntfResult::Success)
+        } else {
+            Err(BusError { errors })
+        }
+    }
+
+    /// Moves every axis to cyclic state. If any axis fails, the axes that
+    /// already moved are requested to go back, and every axis is handed back
+    /// in the state of its drive. See [`BusTransitionError::into_config_bus`]
+    #[allow(clippy::result_large_err)]
+    pub fn into_cyclic(self) -> Result<Bus<Cyclic, INTF, N>, BusTransitionError<INTF, N>> {
+        let mut errors = Vec::new();
+        let moved: Vec<Result<Main<Cyclic, INTF, N>, Main<Config, INTF, N>>> = self
+            .axes
+            .into_iter()
+            .enumerate()
+            .map(|(index, axis)| {
+                axis.into_cyclic().map_err(|(axis, e)| {
+                    errors.push((index, e));
+                    axis
+                })
+            })
+            .collect();
+
+        if errors.is_empty() {
+            return Ok(Bus {
+                axes: moved.into_iter().flatten().collect(),
+            });
+        }
+
+        let axes = moved
+            .into_iter()
+            .enumerate()
+            .map(|(index, axis)| match axis {
+                Ok(axis) => match axis.into_config() {
+                    Ok(axis) => BusAxis::Config(axis),
+                    Err((axis, e)) => {
+                        errors.push((index, e));
+                        BusAxis::Cyclic(axis)
+                    }
+                },
+                Err(axis) => BusAxis::Config(axis),
+            })
+            .collect();
+
+        Err(BusTransitionError { axes, errors })
+    }
+}
+
+/// These functions may be used on any Bus in cyclic State
+impl<INTF, const N: usize> Bus<Cyclic, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Exchanges one cyclic frame with the subnode of every axis. `data`
+    /// holds the cyclic data sent to each axis, in the same order as the
+    /// axes. The received data is only returned if all the axes answered.
+    /// A `data` of another length is rejected before any frame is sent, with
+    /// a single error at the first index missing on either side
+    pub fn cycle(&mut self, data: &[&[u16]]) -> Result<Vec<Vec<u16>>, BusError> {
+        if data.len() != self.axes.len() {
+            return Err(BusError {
+                errors: vec![(data.len().min(self.axes.len()), IntfError::InvalidParameter)],
+            });
+        }
+
+        let mut received = Vec::with_capacity(self.axes.len());
+        let mut errors = Vec::new();
+
+        for (index, (axis, data)) in self.axes.iter_mut().zip(data.iter()).enumerate() {
+            let subnode = axis.subnode();
+            match axis.cycle(subnode, data) {
+                Ok(value) => received.push(value),
+                Err(e) => errors.push((index, e)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(received)
+        } else {
+            Err(BusError { errors })
+        }
+    }
+
+    /// Moves every axis back to config state. If any axis fails, the axes that
+    /// already moved are requested to go back, and every axis is handed back
+    /// in the state of its drive. See [`BusTransitionError::into_cyclic_bus`]
+    #[allow(clippy::result_large_err)]
+    pub fn into_config(self) -> Result<Bus<Config, INTF, N>, BusTransitionError<INTF, N>> {
+        let mut errors = Vec::new();
+        let moved: Vec<Result<Main<Config, INTF, N>, Main<Cyclic, INTF, N>>> = self
+            .axes
+            .into_iter()
+            .enumerate()
+            .map(|(index, axis)| {
+                axis.into_config().map_err(|(axis, e)| {
+                    errors.push((index, e));
+                    axis
+                })
+            })
+            .collect();
+
+        if errors.is_empty() {
+            return Ok(Bus {
+                axes: moved.into_iter().flatten().collect(),
+            });
+        }
+
+        let axes = moved
+            .into_iter()
+            .enumerate()
+            .map(|(index, axis)| match axis {
+                Ok(axis) => match axis.into_cyclic() {
+                    Ok(axis) => BusAxis::Cyclic(axis),
+                    Err((axis, e)) => {
+                        errors.push((index, e));
+                        BusAxis::Config(axis)
+                    }
+                },
+                Err(axis) => BusAxis::Cyclic(axis),
+            })
+            .collect();
+
+        Err(BusTransitionError { axes, errors })
+    }
+}
+
+pub fn create_bus<INTF: PhysicalInterface, const N: usize>(
+    axes: Vec<Main<Init, INTF, N>>,
+) -> Bus<Init, INTF, N> {
+    Bus { axes }
+}
@@ -0,0 +1,281 @@
+use crate::codec::{bytes_to_words, words_to_bytes, ByteOrder};
+use crate::*;
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Magic bytes starting a binary capture log
+const LOG_MAGIC: [u8; 8] = *b"MCBLOG01";
+
+/// Link type of the exported pcap-ng interface (LINKTYPE_USER0)
+pub const PCAPNG_LINKTYPE: u16 = 147;
+
+const PCAPNG_SHB: u32 = 0x0A0D_0D0A;
+const PCAPNG_IDB: u32 = 0x0000_0001;
+const PCAPNG_EPB: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER: u32 = 0x1A2B_3C4D;
+const PCAPNG_OPT_END: u16 = 0;
+const PCAPNG_OPT_EPB_FLAGS: u16 = 2;
+
+/// Whether a frame was sent or received by the wrapped interface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Write,
+    Read,
+}
+
+/// Frame captured by a [`Recorder`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Time elapsed since the capture started
+    pub timestamp: Duration,
+    pub direction: Direction,
+    pub words: Vec<u16>,
+}
+
+/// Captured session, as stored in a binary log
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    /// Start of the capture, since the Unix epoch
+    pub start: Duration,
+    pub records: Vec<Record>,
+}
+
+impl Capture {
+    /// Parses a binary log written by a [`Recorder`]
+    pub fn read_log<R: Read>(mut reader: R) -> io::Result<Capture> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != LOG_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an MCB capture log",
+            ));
+        }
+
+        let start = Duration::from_micros(read_u64(&mut reader)?);
+        let mut records = Vec::new();
+
+        loop {
+            let mut timestamp = [0u8; 8];
+            match reader.read_exact(&mut timestamp) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+
+            let mut header = [0u8; 3];
+            reader.read_exact(&mut header)?;
+            let direction = match header[0] {
+                0 => Direction::Write,
+                1 => Direction::Read,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unknown frame direction",
+                    ))
+                }
+            };
+
+            let mut bytes = vec![0u8; u16::from_le_bytes([header[1], header[2]]) as usize * 2];
+            reader.read_exact(&mut bytes)?;
+
+            records.push(Record {
+                timestamp: Duration::from_micros(u64::from_le_bytes(timestamp)),
+                direction,
+                words: bytes_to_words(&bytes, ByteOrder::Little),
+            });
+        }
+
+        Ok(Capture { start, records })
+    }
+
+    /// Exports the capture as pcap-ng. Every frame is stored as its words in
+    /// little endian, with the direction in the packet flags
+    pub fn write_pcapng<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut shb = Vec::new();
+        shb.extend(PCAPNG_BYTE_ORDER.to_le_bytes());
+        shb.extend(1u16.to_le_bytes());
+        shb.extend(0u16.to_le_bytes());
+        shb.extend((-1i64).to_le_bytes());
+        write_block(&mut writer, PCAPNG_SHB, &shb)?;
+
+        let mut idb = Vec::new();
+        idb.extend(PCAPNG_LINKTYPE.to_le_bytes());
+        idb.extend(0u16.to_le_bytes());
+        idb.extend(0u32.to_le_bytes());
+        write_block(&mut writer, PCAPNG_IDB, &idb)?;
+
+        for record in self.records.iter() {
+            let timestamp = (self.start + record.timestamp).as_micros() as u64;
+            let data = words_to_bytes(&record.words, ByteOrder::Little);
+            let flags: u32 = match record.direction {
+                Direction::Read => 1,
+                Direction::Write => 2,
+            };
+
+            let mut epb = Vec::new();
+            epb.extend(0u32.to_le_bytes());
+            epb.extend(((timestamp >> 32) as u32).to_le_bytes());
+            epb.extend((timestamp as u32).to_le_bytes());
+            epb.extend((data.len() as u32).to_le_bytes());
+            epb.extend((data.len() as u32).to_le_bytes());
+            epb.extend(&data);
+            epb.resize(epb.len().next_multiple_of(4), 0u8);
+            epb.extend(PCAPNG_OPT_EPB_FLAGS.to_le_bytes());
+            epb.extend(4u16.to_le_bytes());
+            epb.extend(flags.to_le_bytes());
+            epb.extend(PCAPNG_OPT_END.to_le_bytes());
+            epb.extend(0u16.to_le_bytes());
+            write_block(&mut writer, PCAPNG_EPB, &epb)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Writes a pcap-ng block. `body` must be padded to 32 bits
+fn write_block<W: Write>(writer: &mut W, kind: u32, body: &[u8]) -> io::Result<()> {
+    let length = (body.len() + 12) as u32;
+    writer.write_all(&kind.to_le_bytes())?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&length.to_le_bytes())
+}
+
+/// Interface wrapper logging every frame written and read by the inner
+/// interface into a binary log. See [`Capture::read_log`]
+pub struct Recorder<INTF: PhysicalInterface, W: Write> {
+    interface: INTF,
+    writer: W,
+    start: Instant,
+}
+
+impl<INTF, W> Recorder<INTF, W>
+where
+    INTF: PhysicalInterface,
+    W: Write,
+{
+    /// Wraps the interface and writes the log header
+    pub fn new(interface: INTF, mut writer: W) -> io::Result<Recorder<INTF, W>> {
+        let start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        writer.write_all(&LOG_MAGIC)?;
+        writer.write_all(&(start.as_micros() as u64).to_le_bytes())?;
+
+        Ok(Recorder {
+            interface,
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Hands back the wrapped interface and the log writer
+    pub fn into_inner(self) -> (INTF, W) {
+        (self.interface, self.writer)
+    }
+
+    fn record(&mut self, direction: Direction, frame: &[u16]) -> io::Result<()> {
+        let timestamp = self.start.elapsed().as_micros() as u64;
+        let direction: u8 = match direction {
+            Direction::Write => 0,
+            Direction::Read => 1,
+        };
+
+        self.writer.write_all(&timestamp.to_le_bytes())?;
+        self.writer.write_all(&[direction])?;
+        self.writer.write_all(&(frame.len() as u16).to_le_bytes())?;
+        self.writer
+            .write_all(&words_to_bytes(frame, ByteOrder::Little))
+    }
+}
+
+/// Frames are logged once the inner interface accepted or delivered them.
+/// If the log cannot be written, the access fails with an interface error
+impl<INTF, W> PhysicalInterface for Recorder<INTF, W>
+where
+    INTF: PhysicalInterface,
+    W: Write,
+{
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        let result = self.interface.raw_write(frame)?;
+        match self.record(Direction::Write, frame) {
+            Ok(()) => Ok(result),
+            Err(_) => Err(IntfError::Interface),
+        }
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        let result = self.interface.raw_read()?;
+        if let IntfResult::Data(data) = &result {
+            if self.record(Direction::Read, &data[..]).is_err() {
+                return Err(IntfError::Interface);
+            }
+        }
+        Ok(result)
+    }
+
+    fn is_data2read(&mut self) -> Result<IntfResult, IntfError> {
+        self.interface.is_data2read()
+    }
+
+    fn crc_checksum(&mut self, frame: &[u16]) -> u16 {
+        self.interface.crc_checksum(frame)
+    }
+
+    fn payload_crc(&mut self, payload: &[u16]) -> u16 {
+        self.interface.payload_crc(payload)
+    }
+}
+
+/// Interface replaying a captured session. Written frames must match the
+/// recorded ones and read frames are taken from the capture, so a Main or a
+/// Node sees exactly the same traffic as when it was recorded
+pub struct Replay {
+    records: VecDeque<Record>,
+}
+
+impl Replay {
+    pub fn new(capture: Capture) -> Replay {
+        Replay {
+            records: capture.records.into(),
+        }
+    }
+
+    /// Checks if every recorded frame was replayed
+    pub fn is_finished(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl PhysicalInterface for Replay {
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        match self.records.front() {
+            Some(record) if record.direction == Direction::Write && record.words == frame => {
+                self.records.pop_front();
+                Ok(IntfResult::Success)
+            }
+            _ => Err(IntfError::Interface),
+        }
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        match self.records.front() {
+            Some(record) if record.direction == Direction::Read => {
+                let data = record.words.clone().into_boxed_slice();
+                self.records.pop_front();
+                Ok(IntfResult::Data(data))
+            }
+            _ => Err(IntfError::Interface),
+        }
+    }
+}
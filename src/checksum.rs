@@ -0,0 +1,90 @@
+use crate::codec::ByteOrder;
+use crate::CRC_IDX;
+
+use crc::{Crc, NoTable, Table, CRC_16_XMODEM};
+
+const XMODEM_TABLE: Crc<u16, Table<1>> = Crc::<u16, Table<1>>::new(&CRC_16_XMODEM);
+const XMODEM_BITWISE: Crc<u16, NoTable> = Crc::<u16, NoTable>::new(&CRC_16_XMODEM);
+
+/// CRC-16/XMODEM calculator used to protect the frames
+pub trait CrcEngine {
+    /// CRC of the words, each one fed as two bytes in the given order
+    fn checksum(&mut self, words: &[u16], order: ByteOrder) -> u16;
+
+    /// CRC of a frame as sent on the bus: the little endian header, command
+    /// and config words. Longer slices are truncated to those words
+    fn frame_crc(&mut self, frame: &[u16]) -> u16 {
+        self.checksum(&frame[..CRC_IDX.min(frame.len())], ByteOrder::Little)
+    }
+}
+
+/// Table driven software CRC. Fastest software option, at the cost of a
+/// 512 byte table
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TableCrc;
+
+impl CrcEngine for TableCrc {
+    fn checksum(&mut self, words: &[u16], order: ByteOrder) -> u16 {
+        let mut digest = XMODEM_TABLE.digest();
+        for word in words {
+            digest.update(&order.to_bytes(*word));
+        }
+        digest.finalize()
+    }
+}
+
+/// Bit by bit software CRC. Needs no table, for MCUs short of flash
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BitwiseCrc;
+
+impl CrcEngine for BitwiseCrc {
+    fn checksum(&mut self, words: &[u16], order: ByteOrder) -> u16 {
+        let mut digest = XMODEM_BITWISE.digest();
+        for word in words {
+            digest.update(&order.to_bytes(*word));
+        }
+        digest.finalize()
+    }
+}
+
+/// Registers of a CRC peripheral such as the STM32 CRC unit, configured for
+/// a 16 bit polynomial 0x1021, initial value 0 and no input or output
+/// reflection
+pub trait CrcUnit {
+    /// Loads the initial value
+    fn reset(&mut self);
+    /// Feeds a half word, most significant bit first
+    fn feed(&mut self, half_word: u16);
+    fn result(&mut self) -> u16;
+}
+
+/// CRC engine backed by a hardware unit. Words are fed as half words, so the
+/// bytes are swapped for little endian frames
+#[derive(Debug, Default)]
+pub struct HardwareCrc<U: CrcUnit> {
+    unit: U,
+}
+
+impl<U: CrcUnit> HardwareCrc<U> {
+    pub fn new(unit: U) -> HardwareCrc<U> {
+        HardwareCrc { unit }
+    }
+
+    pub fn into_inner(self) -> U {
+        self.unit
+    }
+}
+
+impl<U: CrcUnit> CrcEngine for HardwareCrc<U> {
+    fn checksum(&mut self, words: &[u16], order: ByteOrder) -> u16 {
+        self.unit.reset();
+        for word in words {
+            let half_word = match order {
+                ByteOrder::Little => word.swap_bytes(),
+                ByteOrder::Big => *word,
+            };
+            self.unit.feed(half_word);
+        }
+        self.unit.result()
+    }
+}
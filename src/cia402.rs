@@ -0,0 +1,243 @@
+use crate::mcb_main::Main;
+use crate::*;
+
+use std::time::{Duration, Instant};
+
+const DRV_STATE_CONTROL: u16 = 0x010;
+const DRV_STATE_STATUS: u16 = 0x011;
+const DRV_OP_CMD: u16 = 0x014;
+const DRV_OP_VALUE: u16 = 0x015;
+
+const CW_SHUTDOWN: u16 = 0x0006;
+const CW_SWITCH_ON: u16 = 0x0007;
+pub(crate) const CW_ENABLE_OPERATION: u16 = 0x000F;
+const CW_DISABLE_VOLTAGE: u16 = 0x0000;
+const CW_QUICK_STOP: u16 = 0x0002;
+const CW_FAULT_RESET: u16 = 0x0080;
+
+/// Default time to wait for a state transition
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Action taken while driving the state machine to a target state
+enum Step {
+    Done,
+    Wait,
+    Write(u16),
+}
+
+/// States of the power stage state machine
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    NotReadyToSwitchOn,
+    SwitchOnDisabled,
+    ReadyToSwitchOn,
+    SwitchedOn,
+    OperationEnabled,
+    QuickStopActive,
+    FaultReactionActive,
+    Fault,
+    Unknown,
+}
+
+impl State {
+    /// Decodes the state reported by the status word
+    pub fn from_status_word(status: u16) -> State {
+        match status & 0x004F {
+            0x0000 => return State::NotReadyToSwitchOn,
+            0x0040 => return State::SwitchOnDisabled,
+            0x000F => return State::FaultReactionActive,
+            0x0008 => return State::Fault,
+            _ => (),
+        }
+
+        match status & 0x006F {
+            0x0021 => State::ReadyToSwitchOn,
+            0x0023 => State::SwitchedOn,
+            0x0027 => State::OperationEnabled,
+            0x0007 => State::QuickStopActive,
+            _ => State::Unknown,
+        }
+    }
+}
+
+/// Modes of operation of the drive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperationMode {
+    ProfilePosition,
+    Velocity,
+    ProfileVelocity,
+    ProfileTorque,
+    Homing,
+    InterpolatedPosition,
+    CyclicSyncPosition,
+    CyclicSyncVelocity,
+    CyclicSyncTorque,
+    Other(i8),
+}
+
+impl OperationMode {
+    pub fn code(&self) -> i8 {
+        match self {
+            OperationMode::ProfilePosition => 1,
+            OperationMode::Velocity => 2,
+            OperationMode::ProfileVelocity => 3,
+            OperationMode::ProfileTorque => 4,
+            OperationMode::Homing => 6,
+            OperationMode::InterpolatedPosition => 7,
+            OperationMode::CyclicSyncPosition => 8,
+            OperationMode::CyclicSyncVelocity => 9,
+            OperationMode::CyclicSyncTorque => 10,
+            OperationMode::Other(code) => *code,
+        }
+    }
+
+    pub fn from_code(code: i8) -> OperationMode {
+        match code {
+            1 => OperationMode::ProfilePosition,
+            2 => OperationMode::Velocity,
+            3 => OperationMode::ProfileVelocity,
+            4 => OperationMode::ProfileTorque,
+            6 => OperationMode::Homing,
+            7 => OperationMode::InterpolatedPosition,
+            8 => OperationMode::CyclicSyncPosition,
+            9 => OperationMode::CyclicSyncVelocity,
+            10 => OperationMode::CyclicSyncTorque,
+            code => OperationMode::Other(code),
+        }
+    }
+}
+
+/// Handle to the power stage state machine of an axis. See [`Main::cia402`]
+pub struct Cia402<'a, INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    pub(crate) main: &'a mut Main<Config, INTF, N>,
+    pub(crate) subnode: u8,
+    pub(crate) timeout: Duration,
+}
+
+impl<INTF, const N: usize> Main<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Gives access to the state machine of the axis in the given subnode
+    pub fn cia402(&mut self, subnode: u8) -> Cia402<'_, INTF, N> {
+        Cia402 {
+            main: self,
+            subnode,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl<INTF, const N: usize> Cia402<'_, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Changes the time to wait for a state transition
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn status_word(&mut self) -> Result<u16, IntfError> {
+        self.main.read_u16(self.subnode, DRV_STATE_STATUS)
+    }
+
+    pub fn control_word(&mut self, data: u16) -> Result<IntfResult, IntfError> {
+        self.main.write_u16(self.subnode, DRV_STATE_CONTROL, data)
+    }
+
+    pub fn state(&mut self) -> Result<State, IntfError> {
+        self.status_word().map(State::from_status_word)
+    }
+
+    /// Polls the status word until the axis reaches the given state
+    pub fn wait_state(&mut self, target: State) -> Result<IntfResult, IntfError> {
+        self.drive_to(|state| {
+            if state == target {
+                Step::Done
+            } else {
+                Step::Wait
+            }
+        })
+    }
+
+    /// Moves the axis to operation enabled from any non fault state
+    pub fn enable(&mut self) -> Result<IntfResult, IntfError> {
+        self.drive_to(|state| match state {
+            State::OperationEnabled => Step::Done,
+            State::SwitchOnDisabled => Step::Write(CW_SHUTDOWN),
+            State::ReadyToSwitchOn => Step::Write(CW_SWITCH_ON),
+            State::SwitchedOn | State::QuickStopActive => Step::Write(CW_ENABLE_OPERATION),
+            _ => Step::Wait,
+        })
+    }
+
+    /// Disables the power stage
+    pub fn disable(&mut self) -> Result<IntfResult, IntfError> {
+        self.control_word(CW_DISABLE_VOLTAGE)?;
+        self.wait_state(State::SwitchOnDisabled)
+    }
+
+    /// Clears the fault with a rising edge of the fault reset bit
+    pub fn fault_reset(&mut self) -> Result<IntfResult, IntfError> {
+        self.control_word(CW_DISABLE_VOLTAGE)?;
+        self.control_word(CW_FAULT_RESET)?;
+        self.drive_to(|state| match state {
+            State::Fault | State::FaultReactionActive => Step::Wait,
+            _ => Step::Done,
+        })
+    }
+
+    /// Stops the axis with the quick stop deceleration
+    pub fn quick_stop(&mut self) -> Result<IntfResult, IntfError> {
+        self.control_word(CW_QUICK_STOP)?;
+        self.drive_to(|state| match state {
+            State::QuickStopActive | State::SwitchOnDisabled => Step::Done,
+            _ => Step::Wait,
+        })
+    }
+
+    /// Changes the mode of operation and waits until the drive reports it
+    pub fn set_operation_mode(&mut self, mode: OperationMode) -> Result<IntfResult, IntfError> {
+        self.main.write_i8(self.subnode, DRV_OP_CMD, mode.code())?;
+
+        let start = Instant::now();
+        loop {
+            if self.operation_mode()? == mode {
+                return Ok(IntfResult::Success);
+            }
+
+            if start.elapsed() > self.timeout {
+                return Err(IntfError::Timeout);
+            }
+        }
+    }
+
+    pub fn operation_mode(&mut self) -> Result<OperationMode, IntfError> {
+        self.main
+            .read_i8(self.subnode, DRV_OP_VALUE)
+            .map(OperationMode::from_code)
+    }
+
+    /// Polls the state until `next` returns [`Step::Done`], writing the
+    /// control words it requests in between
+    fn drive_to<F>(&mut self, next: F) -> Result<IntfResult, IntfError>
+    where
+        F: Fn(State) -> Step,
+    {
+        let start = Instant::now();
+        loop {
+            let step = next(self.state()?);
+            if let Step::Done = step {
+                return Ok(IntfResult::Success);
+            }
+
+            if start.elapsed() > self.timeout {
+                return Err(IntfError::Timeout);
+            }
+
+            if let Step::Write(control) = step {
+                self.control_word(control)?;
+            }
+        }
+    }
+}
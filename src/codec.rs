@@ -0,0 +1,61 @@
+/// Order of the two bytes of a word. MCB frames are transmitted as little
+/// endian words, whatever the byte order of the host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    /// Byte order of the host, as seen when reinterpreting words as bytes in
+    /// memory
+    pub const fn native() -> ByteOrder {
+        if cfg!(target_endian = "big") {
+            ByteOrder::Big
+        } else {
+            ByteOrder::Little
+        }
+    }
+
+    pub fn to_bytes(&self, word: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::Little => word.to_le_bytes(),
+            ByteOrder::Big => word.to_be_bytes(),
+        }
+    }
+
+    pub fn from_bytes(&self, bytes: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::Little => u16::from_le_bytes(bytes),
+            ByteOrder::Big => u16::from_be_bytes(bytes),
+        }
+    }
+}
+
+/// Splits a list of words into bytes
+pub fn words_to_bytes(words: &[u16], order: ByteOrder) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|word| order.to_bytes(*word))
+        .collect()
+}
+
+/// Packs a list of bytes into words. An odd number of bytes is padded with
+/// zero
+pub fn bytes_to_words(bytes: &[u8], order: ByteOrder) -> Vec<u16> {
+    bytes
+        .chunks(2)
+        .map(|pair| order.from_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+        .collect()
+}
+
+/// Decodes a string stored two characters per word, first character in the
+/// low byte, up to the first zero byte. Bytes map to Latin-1 characters
+pub fn decode_str(words: &[u16]) -> String {
+    words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|byte| *byte != 0)
+        .map(char::from)
+        .collect()
+}
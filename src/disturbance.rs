@@ -0,0 +1,153 @@
+use crate::codec::{bytes_to_words, ByteOrder};
+use crate::mcb_main::Main;
+use crate::monitoring::Channel;
+use crate::*;
+
+/// Subnode hosting the disturbance registers
+pub const DISTURBANCE_SUBNODE: u8 = 0;
+/// Maximum number of registers that can be disturbed at the same time
+pub const MAX_DIST_CHANNELS: usize = 16;
+
+const DIST_ENABLE: u16 = 0x0F0;
+const DIST_STATUS: u16 = 0x0F1;
+const DIST_FREQ_DIV: u16 = 0x0F2;
+const DIST_SAMPLES: u16 = 0x0F3;
+const DIST_REMOVE_DATA: u16 = 0x0F4;
+const DIST_TOTAL_MAP: u16 = 0x100;
+const DIST_REG0_MAP: u16 = 0x101;
+const DIST_DATA: u16 = 0x0B4;
+
+const DIST_STATUS_ENABLED: u16 = 0x0001;
+
+/// Injection settings
+#[derive(Debug, Clone)]
+pub struct DisturbanceConfig {
+    /// Target registers of the injected samples
+    pub channels: Vec<Channel>,
+    /// Sample rate divisor applied to the drive disturbance frequency
+    pub divisor: u32,
+}
+
+/// Handle to the disturbance of a drive. See [`Main::disturbance`]
+pub struct Disturbance<'a, INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    main: &'a mut Main<Config, INTF, N>,
+    config: &'a DisturbanceConfig,
+}
+
+impl<INTF, const N: usize> Main<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Gives access to the disturbance of the drive using the given settings
+    pub fn disturbance<'a>(
+        &'a mut self,
+        config: &'a DisturbanceConfig,
+    ) -> Disturbance<'a, INTF, N> {
+        Disturbance { main: self, config }
+    }
+}
+
+impl<INTF, const N: usize> Disturbance<'_, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Stops any running injection, removes the previous samples and maps
+    /// the target registers
+    pub fn configure(&mut self) -> Result<IntfResult, IntfError> {
+        let config = self.config;
+        if config.channels.is_empty() || config.channels.len() > MAX_DIST_CHANNELS {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        if config
+            .channels
+            .iter()
+            .any(|channel| channel.dtype == DataType::Str)
+        {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        self.stop()?;
+        self.main
+            .write_u16(DISTURBANCE_SUBNODE, DIST_REMOVE_DATA, 1u16)?;
+        self.main
+            .write_u16(DISTURBANCE_SUBNODE, DIST_TOTAL_MAP, 0u16)?;
+
+        for (index, channel) in config.channels.iter().enumerate() {
+            self.main.write_u32(
+                DISTURBANCE_SUBNODE,
+                DIST_REG0_MAP + index as u16,
+                channel.mapping(),
+            )?;
+        }
+
+        self.main.write_u16(
+            DISTURBANCE_SUBNODE,
+            DIST_TOTAL_MAP,
+            config.channels.len() as u16,
+        )?;
+        self.main
+            .write_u32(DISTURBANCE_SUBNODE, DIST_FREQ_DIV, config.divisor)
+    }
+
+    /// Uploads one list of samples per channel, in the same order as the
+    /// configured channels. All the lists must have the same length and the
+    /// data type of the mapped register
+    pub fn upload(&mut self, samples: &[Samples]) -> Result<IntfResult, IntfError> {
+        let config = self.config;
+        if samples.len() != config.channels.len() {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        if config
+            .channels
+            .iter()
+            .zip(samples.iter())
+            .any(|(channel, samples)| channel.dtype != samples.dtype())
+        {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        let length = match samples.first() {
+            Some(first) => first.len(),
+            None => return Err(IntfError::InvalidParameter),
+        };
+
+        if samples.iter().any(|channel| channel.len() != length) {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        let mut buffer = Vec::new();
+        for index in 0..length {
+            for channel in samples.iter() {
+                channel.extend_le(index, &mut buffer);
+            }
+        }
+
+        self.main
+            .write_u32(DISTURBANCE_SUBNODE, DIST_SAMPLES, length as u32)?;
+
+        let block_size = self.main.max_extended_words();
+        for block in bytes_to_words(&buffer, ByteOrder::Little).chunks(block_size) {
+            self.main
+                .write_words(DISTURBANCE_SUBNODE, DIST_DATA, block)?;
+        }
+
+        Ok(IntfResult::Success)
+    }
+
+    /// Starts injecting the uploaded samples into the target registers
+    pub fn start(&mut self) -> Result<IntfResult, IntfError> {
+        self.main.write_u16(DISTURBANCE_SUBNODE, DIST_ENABLE, 1u16)
+    }
+
+    pub fn stop(&mut self) -> Result<IntfResult, IntfError> {
+        self.main.write_u16(DISTURBANCE_SUBNODE, DIST_ENABLE, 0u16)
+    }
+
+    pub fn is_running(&mut self) -> Result<bool, IntfError> {
+        self.main
+            .read_u16(DISTURBANCE_SUBNODE, DIST_STATUS)
+            .map(|status| (status & DIST_STATUS_ENABLED) != 0)
+    }
+}
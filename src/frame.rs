@@ -0,0 +1,302 @@
+use crate::*;
+
+use core::fmt;
+
+/// Number of words of the config part of a frame, CRC included
+const CFG_FRAME_SIZE: usize = 7;
+
+/// Command carried by the command word of a frame, without the extended bit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameCommand {
+    Read,
+    Write,
+    Ack,
+    Error,
+    StateChange,
+    Idle,
+    Unknown(u16),
+}
+
+impl FrameCommand {
+    fn from_code(code: u16) -> FrameCommand {
+        match code & !CFG_EXT_BIT {
+            CFG_STD_READ => FrameCommand::Read,
+            CFG_STD_WRITE => FrameCommand::Write,
+            CFG_STD_ACK => FrameCommand::Ack,
+            CFG_ERR_BIT => FrameCommand::Error,
+            CFG_STATE_CHANGE => FrameCommand::StateChange,
+            CFG_IDLE => FrameCommand::Idle,
+            code => FrameCommand::Unknown(code),
+        }
+    }
+}
+
+/// Issues found while validating a frame. See [`Frame::validate`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameProblem {
+    /// The frame does not hold the config words and the CRC
+    TooShort(usize),
+    /// The CRC word does not match the one computed over the config words
+    Crc { received: u16, computed: u16 },
+    /// The command word holds an unknown command
+    UnknownCommand(u16),
+    /// The extended bit is set in a command that does not support it
+    UnexpectedExtBit,
+    /// The extended payload declares more bytes than the frame holds
+    ExtendedSize { declared: usize, available: usize },
+    /// The target state of a state change request is unknown
+    UnknownTargetState(u16),
+}
+
+impl fmt::Display for FrameProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameProblem::TooShort(size) => write!(
+                f,
+                "frame too short: {} words, at least {} expected",
+                size, CFG_FRAME_SIZE
+            ),
+            FrameProblem::Crc { received, computed } => write!(
+                f,
+                "wrong CRC: received 0x{:04X}, computed 0x{:04X}",
+                received, computed
+            ),
+            FrameProblem::UnknownCommand(code) => write!(f, "unknown command 0x{:X}", code),
+            FrameProblem::UnexpectedExtBit => {
+                write!(f, "extended bit set in a non extended command")
+            }
+            FrameProblem::ExtendedSize {
+                declared,
+                available,
+            } => write!(
+                f,
+                "extended payload of {} bytes but only {} available",
+                declared, available
+            ),
+            FrameProblem::UnknownTargetState(code) => {
+                write!(f, "unknown target state 0x{:X}", code)
+            }
+        }
+    }
+}
+
+/// Single MCB frame. Besides being the buffer used by Main and Node, it can
+/// be built from any list of words, e.g. a capture of a logic analyzer, to
+/// decode its fields. `N` is the number of words the frame holds at most
+#[derive(Clone, Copy)]
+pub struct Frame<const N: usize = MAX_FRAME_SIZE> {
+    pub(crate) subnode: u8,
+    pub(crate) raw: [u16; N],
+    size: usize,
+}
+
+impl Frame {
+    /// Builds a frame from the received words. Words beyond
+    /// [`MAX_FRAME_SIZE`] are ignored
+    pub fn parse(words: &[u16]) -> Frame {
+        let size = words.len().min(MAX_FRAME_SIZE);
+        let mut raw = [0u16; MAX_FRAME_SIZE];
+        raw[..size].copy_from_slice(&words[..size]);
+
+        Frame {
+            subnode: (raw[HEADER_IDX] & 0xfu16) as u8,
+            raw,
+            size,
+        }
+    }
+}
+
+impl<const N: usize> Frame<N> {
+    pub(crate) fn new(subnode: u8) -> Frame<N> {
+        const {
+            assert!(
+                N >= CFG_FRAME_SIZE,
+                "a frame holds at least the config words and the CRC"
+            );
+        }
+        Frame {
+            subnode,
+            raw: [0u16; N],
+            size: 0,
+        }
+    }
+
+    /// Words of the frame
+    pub fn words(&self) -> &[u16] {
+        &self.raw[..self.size]
+    }
+
+    pub fn subnode(&self) -> u8 {
+        self.subnode
+    }
+
+    pub fn address(&self) -> u16 {
+        self.raw[COMMAND_IDX] >> 4
+    }
+
+    pub fn command(&self) -> FrameCommand {
+        FrameCommand::from_code(self.raw[COMMAND_IDX] & 0xfu16)
+    }
+
+    /// Checks if more data follows, either in the next segment or after the CRC
+    pub fn is_extended(&self) -> bool {
+        (self.raw[COMMAND_IDX] & CFG_EXT_BIT) != 0
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.command() == FrameCommand::Error
+    }
+
+    /// Error code of an error frame
+    pub fn error_code(&self) -> Option<u32> {
+        if !self.is_error() {
+            return None;
+        }
+
+        Some((self.raw[CFG_DATA_IDX] as u32) | ((self.raw[CFG_DATA_IDX + 1] as u32) << 16))
+    }
+
+    /// Target state of a state change request
+    pub fn target_state(&self) -> Option<TargetState> {
+        if self.command() != FrameCommand::StateChange {
+            return None;
+        }
+
+        TargetState::from_code(self.raw[CFG_DATA_IDX])
+    }
+
+    /// The four config data words
+    pub fn config_data(&self) -> &[u16] {
+        &self.raw[CFG_DATA_IDX..CRC_IDX.min(self.size.max(CFG_DATA_IDX))]
+    }
+
+    pub fn crc(&self) -> Option<u16> {
+        if self.size > CRC_IDX {
+            Some(self.raw[CRC_IDX])
+        } else {
+            None
+        }
+    }
+
+    /// Words following the CRC of an idle frame
+    pub fn cyclic_data(&self) -> &[u16] {
+        if self.command() != FrameCommand::Idle || self.size <= CYC_DATA_IDX {
+            return &[];
+        }
+
+        &self.raw[CYC_DATA_IDX..self.size]
+    }
+
+    /// Payload following the CRC of an extended frame. Its size in bytes is
+    /// given by the first config data word
+    pub fn extended_data(&self) -> &[u16] {
+        if !self.is_extended() || self.command() == FrameCommand::Read || self.size <= EXT_DATA_IDX
+        {
+            return &[];
+        }
+
+        let size = (self.raw[CFG_DATA_IDX] as usize).div_ceil(2);
+        &self.raw[EXT_DATA_IDX..self.size.min(EXT_DATA_IDX + size)]
+    }
+
+    /// Checks the frame using the default CRC. See [`Frame::validate_with`]
+    pub fn validate(&self) -> Vec<FrameProblem> {
+        self.validate_with(default_crc)
+    }
+
+    /// Checks the frame using the given CRC function, which receives the
+    /// config words as [`PhysicalInterface::crc_checksum`] does
+    pub fn validate_with<F>(&self, mut crc: F) -> Vec<FrameProblem>
+    where
+        F: FnMut(&[u16]) -> u16,
+    {
+        let mut problems = Vec::new();
+        if self.size < CFG_FRAME_SIZE {
+            problems.push(FrameProblem::TooShort(self.size));
+            return problems;
+        }
+
+        let computed = crc(&self.raw[..CRC_IDX]);
+        if computed != self.raw[CRC_IDX] {
+            problems.push(FrameProblem::Crc {
+                received: self.raw[CRC_IDX],
+                computed,
+            });
+        }
+
+        match self.command() {
+            FrameCommand::Unknown(code) => problems.push(FrameProblem::UnknownCommand(code)),
+            FrameCommand::Error | FrameCommand::StateChange | FrameCommand::Idle
+                if self.is_extended() =>
+            {
+                problems.push(FrameProblem::UnexpectedExtBit)
+            }
+            FrameCommand::StateChange if self.target_state().is_none() => {
+                problems.push(FrameProblem::UnknownTargetState(self.raw[CFG_DATA_IDX]))
+            }
+            _ => (),
+        }
+
+        if self.size > EXT_DATA_IDX && !self.extended_data().is_empty() {
+            let declared = self.raw[CFG_DATA_IDX] as usize;
+            let available = (self.size - EXT_DATA_IDX) * 2;
+            if declared > available {
+                problems.push(FrameProblem::ExtendedSize {
+                    declared,
+                    available,
+                });
+            }
+        }
+
+        problems
+    }
+}
+
+impl<const N: usize> fmt::Debug for Frame<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frame")
+            .field("subnode", &self.subnode)
+            .field("words", &self.words())
+            .finish()
+    }
+}
+
+/// Prints the decoded fields in a single line, e.g.
+/// `subnode 1 | Write | address 0x00A | config [0001, 0000, 0000, 0000] | crc 0x1D0F`
+impl<const N: usize> fmt::Display for Frame<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.size <= COMMAND_IDX {
+            return write!(f, "truncated frame {:04X?}", self.words());
+        }
+
+        write!(f, "subnode {} | {:?}", self.subnode, self.command())?;
+        if self.is_extended() {
+            write!(f, " ext")?;
+        }
+        write!(f, " | address 0x{:03X}", self.address())?;
+
+        if let Some(code) = self.error_code() {
+            write!(f, " | error 0x{:08X}", code)?;
+        } else if let Some(target) = self.target_state() {
+            write!(f, " | target {:?}", target)?;
+        } else {
+            write!(f, " | config {:04X?}", self.config_data())?;
+        }
+
+        if let Some(crc) = self.crc() {
+            write!(f, " | crc 0x{:04X}", crc)?;
+        }
+
+        let cyclic = self.cyclic_data();
+        if !cyclic.is_empty() {
+            write!(f, " | cyclic {:04X?}", cyclic)?;
+        }
+
+        let extended = self.extended_data();
+        if !extended.is_empty() {
+            write!(f, " | extended {:04X?}", extended)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::mcb_main::Main;
+use crate::mcb_node::{CommandType, Node};
+use crate::*;
+
+/// Error code answered when the drive does not answer or its answer is
+/// corrupted
+pub const ERR_DRIVE_UNREACHABLE: u32 = 0x0800_0000;
+/// Error code answered to state change requests. The gateway only forwards
+/// config accesses
+pub const ERR_STATE_CHANGE: u32 = 0x0800_0022;
+
+/// Bridge between two interfaces. Config requests received by the Node are
+/// forwarded through the Main and the answers of the drive, including errors,
+/// are sent back. Each side uses its own extended mode, so extended and
+/// segmented transfers are translated transparently
+pub struct Gateway<
+    NINTF: PhysicalInterface,
+    MINTF: PhysicalInterface,
+    const N: usize = MAX_FRAME_SIZE,
+> {
+    node: Node<Config, NINTF, N>,
+    main: Main<Config, MINTF, N>,
+    routes: Vec<(u8, u8)>,
+}
+
+impl<NINTF, MINTF, const N: usize> Gateway<NINTF, MINTF, N>
+where
+    NINTF: PhysicalInterface,
+    MINTF: PhysicalInterface,
+{
+    /// Forwards the requests addressed to `node_subnode` to `main_subnode`.
+    /// The Node starts serving `node_subnode` if it did not yet. Subnodes
+    /// without route are forwarded unchanged
+    pub fn route(&mut self, node_subnode: u8, main_subnode: u8) {
+        self.node.add_subnode(node_subnode);
+        self.routes.retain(|(from, _)| *from != node_subnode);
+        self.routes.push((node_subnode, main_subnode));
+    }
+
+    fn target(&self, subnode: u8) -> u8 {
+        self.routes
+            .iter()
+            .find(|(from, _)| *from == subnode)
+            .map_or(subnode, |(_, to)| *to)
+    }
+
+    /// Waits for a request on the Node and forwards it. Failures of the drive
+    /// are answered as error frames, so only failures of the Node side are
+    /// returned
+    pub fn process(&mut self) -> Result<IntfResult, IntfError> {
+        let request = self.node.read()?;
+        let subnode = self.target(request.subnode);
+
+        let result = match request.command {
+            CommandType::Read | CommandType::ExtRead => {
+                match self.main.read_words(subnode, request.address) {
+                    Ok(words) => return self.node.write_words(request.address, &words),
+                    Err(e) => Err(e),
+                }
+            }
+            CommandType::Write | CommandType::ExtWrite => {
+                let words = self.node.get_data_words(&request).to_vec();
+                self.main.write_words(subnode, request.address, &words)
+            }
+            CommandType::StateChange(_) => return self.node.reject_state_change(ERR_STATE_CHANGE),
+            CommandType::Idle => return Err(IntfError::WrongCommand),
+        };
+
+        match result {
+            Ok(_) => self.node.ack(request.address),
+            Err(IntfError::Access(code)) => self.node.error(0, code),
+            Err(_) => self.node.error(0, ERR_DRIVE_UNREACHABLE),
+        }
+    }
+
+    /// Forwards requests until the interface of the Node fails. Frames that
+    /// cannot be decoded are dropped
+    pub fn run(&mut self) -> IntfError {
+        loop {
+            if let Err(IntfError::Interface) = self.process() {
+                return IntfError::Interface;
+            }
+        }
+    }
+
+    /// Hands back the Node and the Main
+    pub fn into_parts(self) -> (Node<Config, NINTF, N>, Main<Config, MINTF, N>) {
+        (self.node, self.main)
+    }
+}
+
+pub fn create_gateway<NINTF: PhysicalInterface, MINTF: PhysicalInterface, const N: usize>(
+    node: Node<Config, NINTF, N>,
+    main: Main<Config, MINTF, N>,
+) -> Gateway<NINTF, MINTF, N> {
+    Gateway {
+        node,
+        main,
+        routes: Vec::new(),
+    }
+}
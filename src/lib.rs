@@ -1,5 +1,6 @@
 #![crate_type = "lib"]
 #![crate_name = "mcb"]
+#![forbid(unsafe_code)]
 
 //! # mcb-rs
 //!
@@ -55,12 +56,47 @@
 //!  * [Firmware](https://github.com/javifercep/turonet-rs)
 //!
 
+/// Module implementing the access to the registers of a single subnode
+pub mod axis;
+/// Module implementing a manager of several Main devices
+pub mod bus;
+/// Module implementing the capture and replay of the frames of an interface
+pub mod capture;
+/// Module implementing the CRC engines protecting the frames
+pub mod checksum;
+/// Module implementing the CiA-402 power stage state machine
+pub mod cia402;
+/// Module implementing the conversions between words, bytes and strings
+pub mod codec;
+/// Module implementing the drive disturbance injection feature
+pub mod disturbance;
+/// Module implementing the frame model and decoder
+pub mod frame;
+/// Module implementing a gateway between a Node and a Main
+pub mod gateway;
 /// Module implementing Main devices
 pub mod mcb_main;
 /// Module implementing Node devices
 pub mod mcb_node;
+/// Module implementing the drive monitoring (scope) feature
+pub mod monitoring;
+/// Module implementing homing, profile position and profile velocity commands
+pub mod motion;
+/// Module implementing the save and restore of parameter sets
+pub mod parameters;
+/// Module implementing the cycle time scheduler of the cyclic exchange
+pub mod scheduler;
+/// Module implementing a Main shared between threads
+pub mod shared;
+/// Module implementing the statistics of the traffic
+pub mod stats;
+/// Module implementing the optional instrumentation of the transactions
+mod trace;
+/// Module implementing the cyclic watchdog of Node devices
+pub mod watchdog;
 
-/// Maximum size of a single frame
+/// Default size of a single frame. See [`mcb_main::create_sized_main_mcb`]
+/// and [`mcb_node::create_sized_node_mcb`] for other sizes
 pub const MAX_FRAME_SIZE: usize = 128;
 
 const MAX_STD_CFG_DATA: usize = 8;
@@ -73,14 +109,21 @@ const CFG_STD_WRITE: u16 = 0x0004;
 const CFG_EXT_WRITE: u16 = CFG_STD_WRITE | CFG_EXT_BIT;
 const CFG_STD_ACK: u16 = 0x0006;
 const CFG_EXT_ACK: u16 = CFG_STD_ACK | CFG_EXT_BIT;
+const CFG_STATE_CHANGE: u16 = 0x000A;
 const CFG_IDLE: u16 = 0x000E;
 
+const STATE_CONFIG: u16 = 0x0001;
+const STATE_CYCLIC: u16 = 0x0002;
+
 const MAX_ADDRESS: u16 = 0x0FFF;
 
 const HEADER_IDX: usize = 0;
 const COMMAND_IDX: usize = 1;
 const CFG_DATA_IDX: usize = 2;
-const CYC_DATA_IDX: usize = 6;
+const CRC_IDX: usize = 6;
+const EXT_DATA_IDX: usize = 7;
+// Cyclic data follows the CRC of the config words
+const CYC_DATA_IDX: usize = 7;
 
 /// Successful results of an MCB access
 #[derive(Debug)]
@@ -88,11 +131,14 @@ pub enum IntfResult {
     Success,
     Empty,
     Ready,
-    Data(Box<[u16; MAX_FRAME_SIZE]>),
+    /// Received frame. Main and Node pad it to their frame size and reject
+    /// longer ones
+    Data(Box<[u16]>),
 }
 
 /// Error results of an MCB access
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum IntfError {
     Interface,
     WrongCommand,
@@ -100,6 +146,38 @@ pub enum IntfError {
     Access(u32),
     AddressOutOfIndex,
     Crc,
+    InvalidParameter,
+    Timeout,
+    Motion(u16),
+    /// The payload of an extended frame does not match its trailing CRC
+    PayloadCrc,
+    /// The cyclic link is considered lost: the Main missed too many answers
+    /// in a row, or the watchdog of the Node expired
+    LinkLoss,
+}
+
+/// Communication states a state change request can move to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetState {
+    Config,
+    Cyclic,
+}
+
+impl TargetState {
+    fn code(&self) -> u16 {
+        match self {
+            TargetState::Config => STATE_CONFIG,
+            TargetState::Cyclic => STATE_CYCLIC,
+        }
+    }
+
+    fn from_code(code: u16) -> Option<TargetState> {
+        match code {
+            STATE_CONFIG => Some(TargetState::Config),
+            STATE_CYCLIC => Some(TargetState::Cyclic),
+            _ => None,
+        }
+    }
 }
 
 /// How extended frames are transmitted
@@ -109,12 +187,230 @@ pub enum ExtMode {
     Extended,
 }
 
-#[derive(Clone, Copy)]
-struct Frame {
-    _address: u16,
-    subnode: u8,
-    raw: [u16; MAX_FRAME_SIZE],
+/// Data types of the values stored in the registers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    Str,
 }
+
+impl DataType {
+    /// Size in bytes of a single value. Strings have no fixed size
+    pub fn size(&self) -> usize {
+        match self {
+            DataType::U8 | DataType::I8 => 1,
+            DataType::U16 | DataType::I16 => 2,
+            DataType::U32 | DataType::I32 | DataType::F32 => 4,
+            DataType::U64 | DataType::I64 | DataType::F64 => 8,
+            DataType::Str => 0,
+        }
+    }
+
+    /// Lower case name of the type, as used in parameter files
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::U8 => "u8",
+            DataType::I8 => "i8",
+            DataType::U16 => "u16",
+            DataType::I16 => "i16",
+            DataType::U32 => "u32",
+            DataType::I32 => "i32",
+            DataType::U64 => "u64",
+            DataType::I64 => "i64",
+            DataType::F32 => "f32",
+            DataType::F64 => "f64",
+            DataType::Str => "str",
+        }
+    }
+
+    /// Type with the given name, ignoring case
+    pub fn from_name(name: &str) -> Option<DataType> {
+        [
+            DataType::U8,
+            DataType::I8,
+            DataType::U16,
+            DataType::I16,
+            DataType::U32,
+            DataType::I32,
+            DataType::U64,
+            DataType::I64,
+            DataType::F32,
+            DataType::F64,
+            DataType::Str,
+        ]
+        .into_iter()
+        .find(|dtype| dtype.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Typed list of values sampled from (or injected into) a register
+#[derive(Debug, Clone, PartialEq)]
+pub enum Samples {
+    U8(Vec<u8>),
+    I8(Vec<i8>),
+    U16(Vec<u16>),
+    I16(Vec<i16>),
+    U32(Vec<u32>),
+    I32(Vec<i32>),
+    U64(Vec<u64>),
+    I64(Vec<i64>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+impl Samples {
+    /// Creates an empty list for the given data type. Strings cannot be sampled
+    pub fn new(dtype: DataType) -> Option<Samples> {
+        match dtype {
+            DataType::U8 => Some(Samples::U8(Vec::new())),
+            DataType::I8 => Some(Samples::I8(Vec::new())),
+            DataType::U16 => Some(Samples::U16(Vec::new())),
+            DataType::I16 => Some(Samples::I16(Vec::new())),
+            DataType::U32 => Some(Samples::U32(Vec::new())),
+            DataType::I32 => Some(Samples::I32(Vec::new())),
+            DataType::U64 => Some(Samples::U64(Vec::new())),
+            DataType::I64 => Some(Samples::I64(Vec::new())),
+            DataType::F32 => Some(Samples::F32(Vec::new())),
+            DataType::F64 => Some(Samples::F64(Vec::new())),
+            DataType::Str => None,
+        }
+    }
+
+    /// Data type of the stored values
+    pub fn dtype(&self) -> DataType {
+        match self {
+            Samples::U8(_) => DataType::U8,
+            Samples::I8(_) => DataType::I8,
+            Samples::U16(_) => DataType::U16,
+            Samples::I16(_) => DataType::I16,
+            Samples::U32(_) => DataType::U32,
+            Samples::I32(_) => DataType::I32,
+            Samples::U64(_) => DataType::U64,
+            Samples::I64(_) => DataType::I64,
+            Samples::F32(_) => DataType::F32,
+            Samples::F64(_) => DataType::F64,
+        }
+    }
+
+    /// Number of stored values
+    pub fn len(&self) -> usize {
+        match self {
+            Samples::U8(v) => v.len(),
+            Samples::I8(v) => v.len(),
+            Samples::U16(v) => v.len(),
+            Samples::I16(v) => v.len(),
+            Samples::U32(v) => v.len(),
+            Samples::I32(v) => v.len(),
+            Samples::U64(v) => v.len(),
+            Samples::I64(v) => v.len(),
+            Samples::F32(v) => v.len(),
+            Samples::F64(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends the little endian representation of the value at `index`
+    fn extend_le(&self, index: usize, buffer: &mut Vec<u8>) {
+        match self {
+            Samples::U8(v) => buffer.push(v[index]),
+            Samples::I8(v) => buffer.push(v[index] as u8),
+            Samples::U16(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::I16(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::U32(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::I32(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::U64(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::I64(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::F32(v) => buffer.extend(v[index].to_le_bytes()),
+            Samples::F64(v) => buffer.extend(v[index].to_le_bytes()),
+        }
+    }
+
+    /// Appends a value decoded from its little endian representation.
+    /// `bytes` must contain exactly `self.dtype().size()` bytes
+    fn push_le(&mut self, bytes: &[u8]) {
+        match self {
+            Samples::U8(v) => v.push(bytes[0]),
+            Samples::I8(v) => v.push(bytes[0] as i8),
+            Samples::U16(v) => v.push(u16::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::I16(v) => v.push(i16::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::U32(v) => v.push(u32::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::I32(v) => v.push(i32::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::U64(v) => v.push(u64::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::I64(v) => v.push(i64::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::F32(v) => v.push(f32::from_le_bytes(bytes.try_into().unwrap())),
+            Samples::F64(v) => v.push(f64::from_le_bytes(bytes.try_into().unwrap())),
+        }
+    }
+}
+
+/// CRC used when the interface does not provide its own one
+fn default_crc(frame: &[u16]) -> u16 {
+    use checksum::CrcEngine;
+    checksum::TableCrc.frame_crc(frame)
+}
+
+/// Appends the payload CRC to an extended frame holding `words` payload words.
+/// Returns the size of the frame including the CRC
+fn seal_payload<INTF: PhysicalInterface>(
+    interface: &mut INTF,
+    frame: &mut [u16],
+    words: usize,
+) -> Result<usize, IntfError> {
+    let crc_idx = EXT_DATA_IDX + words;
+    if crc_idx >= frame.len() {
+        return Err(IntfError::InvalidParameter);
+    }
+
+    frame[crc_idx] = interface.payload_crc(&frame[EXT_DATA_IDX..crc_idx]);
+    Ok(crc_idx + 1)
+}
+
+/// Copies a received frame into a buffer of `N` words, missing words being
+/// zero. Frames of `N` words are not copied and longer ones are rejected
+fn fit_frame<const N: usize>(words: Box<[u16]>) -> Result<Box<[u16; N]>, IntfError> {
+    if words.len() > N {
+        return Err(IntfError::InvalidParameter);
+    }
+    match words.try_into() {
+        Ok(frame) => Ok(frame),
+        Err(words) => {
+            let words: Box<[u16]> = words;
+            let mut frame = Box::new([0u16; N]);
+            frame[..words.len()].copy_from_slice(&words);
+            Ok(frame)
+        }
+    }
+}
+
+/// Checks the trailing CRC of a received extended frame. The payload size is
+/// taken from the first config word
+fn check_payload<INTF: PhysicalInterface>(
+    interface: &mut INTF,
+    frame: &[u16],
+) -> Result<(), IntfError> {
+    let crc_idx = EXT_DATA_IDX + (frame[CFG_DATA_IDX] as usize).div_ceil(2);
+    if crc_idx >= frame.len() {
+        return Err(IntfError::InvalidParameter);
+    }
+
+    if frame[crc_idx] != interface.payload_crc(&frame[EXT_DATA_IDX..crc_idx]) {
+        return Err(IntfError::PayloadCrc);
+    }
+    Ok(())
+}
+
 /// This trait contains the implementation required to access to the Network/Bus
 pub trait PhysicalInterface {
     /// This function is called everytime the procotol needs to access
@@ -136,11 +432,20 @@ pub trait PhysicalInterface {
     }
 
     /// This trait is availabble to offer the option to compute the CRC through a HW
-    /// accelerator or dedicated peripheral. Otherwise, the default implementation is
-    /// available
+    /// accelerator or dedicated peripheral, usually by calling
+    /// [`checksum::CrcEngine::frame_crc`] on one of the engines of the
+    /// [`checksum`] module. Otherwise, the default table driven implementation
+    /// is available
     fn crc_checksum(&mut self, frame: &[u16]) -> u16 {
-        const XMODEM: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
-        unsafe { XMODEM.checksum(frame[..6].align_to::<u8>().1) }
+        default_crc(frame)
+    }
+
+    /// CRC of the payload of an extended frame, appended after it when the
+    /// Main or Node enables the payload CRC. Same options as
+    /// [`PhysicalInterface::crc_checksum`]
+    fn payload_crc(&mut self, payload: &[u16]) -> u16 {
+        use checksum::CrcEngine;
+        checksum::TableCrc.checksum(payload, codec::ByteOrder::Little)
     }
 }
 
@@ -1,30 +1,82 @@
+use crate::frame::Frame;
+use crate::parameters::Value;
+use crate::stats::Statistics;
+use crate::trace::{self, Transaction};
 use crate::*;
 
-/// Mcb State interface
-pub struct Main<STATE, INTERFACE: PhysicalInterface> {
-    frame: Frame,
+use std::time::{Duration, Instant};
+
+/// Mcb State interface. `N` is the size of the frames in words, see
+/// [`create_sized_main_mcb`]
+pub struct Main<STATE, INTERFACE: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    frame: Frame<N>,
     _state: STATE,
     interface: INTERFACE,
     ext_mode: ExtMode,
+    payload_crc: bool,
+    retries: u32,
+    stats: Statistics,
+    link_loss_threshold: u32,
+    response_timeout: Option<Duration>,
+    missed_cycles: u32,
+    access: Option<CyclicAccess>,
+    /// Last cyclic data sent to each subnode
+    outputs: Vec<(u8, Vec<u16>)>,
+    /// Subnodes whose next frame must be idle after an aborted access
+    resync: Vec<u8>,
 }
 
-/// These functions may be used on any Mcb struct
-impl<INTF> Main<Init, INTF>
-where
-    INTF: PhysicalInterface,
-{
-    pub fn init(self) -> Main<Config, INTF> {
-        Main {
-            frame: self.frame,
-            _state: Config,
-            interface: self.interface,
-            ext_mode: self.ext_mode,
+/// Config access carried by the config words of cyclic frames. Data longer
+/// than the four config words is transferred in segments, one per frame
+struct CyclicAccess {
+    subnode: u8,
+    add: u16,
+    cmd: u16,
+    /// Words to write, and the number of them acknowledged
+    data: Vec<u16>,
+    sent: usize,
+    /// Words read so far
+    received: Vec<u16>,
+    result: Option<Result<Vec<u16>, IntfError>>,
+}
+
+impl CyclicAccess {
+    /// Command word and config words of the next frame
+    fn request(&self) -> (u16, [u16; 4]) {
+        let mut words = [0u16; 4];
+        if self.cmd == CFG_STD_READ {
+            return (CFG_STD_READ + (self.add << 4), words);
+        }
+
+        let end = (self.sent + words.len()).min(self.data.len());
+        words[..end - self.sent].copy_from_slice(&self.data[self.sent..end]);
+        let cmd = match end < self.data.len() {
+            true => CFG_EXT_WRITE,
+            false => CFG_STD_WRITE,
+        };
+        (cmd + (self.add << 4), words)
+    }
+
+    /// Takes in an acknowledge of the node. The access completes with the
+    /// last segment
+    fn acknowledge(&mut self, data: &[u16]) {
+        if self.cmd == CFG_STD_READ {
+            self.received
+                .extend_from_slice(&data[CFG_DATA_IDX..CRC_IDX]);
+            if (data[COMMAND_IDX] & CFG_EXT_BIT) != CFG_EXT_BIT {
+                self.result = Some(Ok(core::mem::take(&mut self.received)));
+            }
+        } else {
+            self.sent = (self.sent + MAX_STD_CFG_DATA / 2).min(self.data.len());
+            if self.sent == self.data.len() {
+                self.result = Some(Ok(Vec::new()));
+            }
         }
     }
 }
 
-/// These functions may be used on any Mcb in config State
-impl<INTF> Main<Config, INTF>
+/// These functions may be used on any Mcb struct
+impl<STATE, INTF, const N: usize> Main<STATE, INTF, N>
 where
     INTF: PhysicalInterface,
 {
@@ -33,6 +85,29 @@ where
         subnode: u8,
         add: u16,
         cmd: u16,
+    ) -> Result<IntfResult, IntfError> {
+        self.internal_sized_access(subnode, add, cmd, 7)
+    }
+
+    fn internal_sized_access(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        cmd: u16,
+        size: usize,
+    ) -> Result<IntfResult, IntfError> {
+        let transaction = Transaction::begin("access", subnode, add, cmd);
+        let result = self.repeated_access(subnode, add, cmd, size);
+        transaction.end(&result);
+        result
+    }
+
+    fn repeated_access(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        cmd: u16,
+        size: usize,
     ) -> Result<IntfResult, IntfError> {
         if add > MAX_ADDRESS {
             return Err(IntfError::AddressOutOfIndex);
@@ -42,41 +117,218 @@ where
         self.frame.raw[COMMAND_IDX] = cmd + (add << 4);
         self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw[..6]);
 
-        let built_frame = &self.frame.raw[..7];
+        let mut attempt = 0;
+        loop {
+            match self.round_trip(subnode, add, size) {
+                Err(e @ (IntfError::Interface | IntfError::Crc | IntfError::Timeout))
+                    if cmd == CFG_STD_READ && attempt < self.retries =>
+                {
+                    attempt += 1;
+                    self.stats.retries += 1;
+                    trace::retry(attempt, &e);
+                }
+                result => return result,
+            }
+        }
+    }
 
-        match self.interface.raw_write(built_frame) {
+    /// Sends the built frame and checks that the answer acknowledges it
+    fn round_trip(&mut self, subnode: u8, add: u16, size: usize) -> Result<IntfResult, IntfError> {
+        let start = Instant::now();
+        match self.send(size) {
             Ok(IntfResult::Success) => (),
             _ => return Err(IntfError::Interface),
         }
 
+        let data = self.receive()?;
+        self.stats.latency.record(start.elapsed());
+
+        if (data[0] & 0xfu16) != subnode as u16 {
+            self.stats.subnode_mismatches += 1;
+            return Err(IntfError::Access(0u32));
+        }
+
+        if (data[1] & 0xfffeu16) != (CFG_STD_ACK + (add << 4)) {
+            let code = (data[2] as u32) | ((data[3] as u32) << 16);
+            if (data[1] & 0xf & !CFG_EXT_BIT) == CFG_ERR_BIT {
+                self.stats.access_error(code);
+            } else {
+                self.stats.wrong_commands += 1;
+            }
+            return Err(IntfError::Access(code));
+        }
+
+        Ok(IntfResult::Data(data))
+    }
+
+    /// Writes the first `size` words of the frame to the interface
+    fn send(&mut self, size: usize) -> Result<IntfResult, IntfError> {
+        let result = self.interface.raw_write(&self.frame.raw[..size]);
+        if result.is_ok() {
+            self.stats.frames_sent += 1;
+        }
+        result
+    }
+
+    /// Waits for a frame and checks its CRC
+    fn receive(&mut self) -> Result<Box<[u16; N]>, IntfError> {
+        let start = Instant::now();
         let mut is_ready = self.interface.is_data2read();
 
         while let Ok(IntfResult::Empty) = is_ready {
+            if matches!(self.response_timeout, Some(timeout) if start.elapsed() >= timeout) {
+                self.stats.timeouts += 1;
+                return Err(IntfError::Timeout);
+            }
             is_ready = self.interface.is_data2read();
         }
 
         let data = match self.interface.raw_read() {
             Ok(IntfResult::Data(value)) => value,
+            Err(IntfError::Timeout) => {
+                self.stats.timeouts += 1;
+                return Err(IntfError::Interface);
+            }
             _ => return Err(IntfError::Interface),
         };
+        self.stats.frames_received += 1;
+
+        let Ok(data) = fit_frame(data) else {
+            self.stats.oversized_frames += 1;
+            return Err(IntfError::InvalidParameter);
+        };
 
         if data[6] != self.interface.crc_checksum(&data[..6]) {
+            self.stats.crc_errors += 1;
             return Err(IntfError::Crc);
         }
 
-        if (data[0] & 0xfu16) != subnode as u16 {
-            return Err(IntfError::Access(0u32));
+        Ok(data)
+    }
+
+    /// Number of times a read is repeated when the answer is lost or
+    /// corrupted. Writes are never repeated, as the node may have applied
+    /// them before the answer was lost. Reads are not repeated by default
+    pub fn set_retries(&mut self, retries: u32) {
+        self.retries = retries;
+    }
+
+    /// Number of failed cycles in a row after which the link is considered
+    /// lost. Link loss detection is disabled with 0, the default
+    pub fn set_link_loss_threshold(&mut self, cycles: u32) {
+        self.link_loss_threshold = cycles;
+    }
+
+    /// Time to wait for an answer before failing with [`IntfError::Timeout`],
+    /// which counts as a missed cycle in cyclic state. The wait polls
+    /// [`PhysicalInterface::is_data2read`], so an interface with a blocking
+    /// `raw_read` must report [`IntfResult::Empty`] while no frame arrived.
+    /// Answers are awaited forever by default
+    pub fn set_response_timeout(&mut self, timeout: Option<Duration>) {
+        self.response_timeout = timeout;
+    }
+
+    /// Snapshot of the traffic counters
+    pub fn statistics(&self) -> Statistics {
+        self.stats.clone()
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.stats = Statistics::default();
+    }
+
+    /// Subnode the Main was created for. State changes are requested to it
+    /// and [`Main::default_axis`] accesses its registers
+    pub fn subnode(&self) -> u8 {
+        self.frame.subnode
+    }
+
+    /// Protects the payload of extended frames with a trailing CRC, computed
+    /// by [`PhysicalInterface::payload_crc`]. Mismatches are reported as
+    /// [`IntfError::PayloadCrc`]. The node must be configured the same way.
+    /// Segmented transfers are not affected
+    pub fn set_payload_crc(&mut self, enabled: bool) {
+        self.payload_crc = enabled;
+    }
+
+    pub fn has_payload_crc(&self) -> bool {
+        self.payload_crc
+    }
+
+    /// Size of the frames in words
+    pub fn frame_size(&self) -> usize {
+        N
+    }
+
+    /// Largest payload, in words, of an extended frame. Longer lists of
+    /// words must be split by the caller
+    pub fn max_extended_words(&self) -> usize {
+        N.saturating_sub(EXT_DATA_IDX + self.payload_crc as usize)
+    }
+
+    /// Largest cyclic data, in words, exchanged in a cycle
+    pub fn max_cyclic_words(&self) -> usize {
+        N - CYC_DATA_IDX
+    }
+
+    /// Checks the payload CRC of an extended answer, if enabled
+    fn check_ext_answer(&mut self, data: &[u16]) -> Result<(), IntfError> {
+        if !self.payload_crc || (data[COMMAND_IDX] & CFG_EXT_BIT) != CFG_EXT_BIT {
+            return Ok(());
         }
 
-        if (data[1] & 0xfffeu16) != (CFG_STD_ACK + (add << 4)) {
-            return Err(IntfError::Access(
-                (data[2] as u32) | ((data[3] as u32) << 16),
-            ));
+        let result = check_payload(&mut self.interface, data);
+        if let Err(IntfError::PayloadCrc) = result {
+            self.stats.payload_crc_errors += 1;
         }
+        result
+    }
 
-        Ok(IntfResult::Data(data))
+    /// Requests the node to move to the target state
+    fn state_change(&mut self, target: TargetState) -> Result<IntfResult, IntfError> {
+        let subnode = self.frame.subnode;
+        self.frame.raw[CFG_DATA_IDX..EXT_DATA_IDX - 1].fill(0u16);
+        self.frame.raw[CFG_DATA_IDX] = target.code();
+
+        self.internal_access(subnode, 0, CFG_STATE_CHANGE)
     }
 
+    /// Changes the typestate without talking to the node
+    pub(crate) fn transition<NEXT>(self, state: NEXT) -> Main<NEXT, INTF, N> {
+        Main {
+            frame: self.frame,
+            _state: state,
+            interface: self.interface,
+            ext_mode: self.ext_mode,
+            payload_crc: self.payload_crc,
+            retries: self.retries,
+            stats: self.stats,
+            link_loss_threshold: self.link_loss_threshold,
+            response_timeout: self.response_timeout,
+            missed_cycles: 0,
+            access: None,
+            outputs: Vec::new(),
+            resync: Vec::new(),
+        }
+    }
+}
+
+impl<INTF, const N: usize> Main<Init, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    pub fn init(self) -> Main<Config, INTF, N> {
+        self.transition(Config)
+    }
+}
+
+/// These functions may be used on any Mcb in config State. They are the raw
+/// access: every register is addressed by its subnode explicitly. See
+/// [`Main::axis`] for an access bound to a single subnode
+impl<INTF, const N: usize> Main<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
     pub fn write_u8(&mut self, subnode: u8, add: u16, data: u8) -> Result<IntfResult, IntfError> {
         self.frame.raw[CFG_DATA_IDX] = data as u16;
 
@@ -208,33 +460,19 @@ where
     }
 
     pub fn write_f32(&mut self, subnode: u8, add: u16, data: f32) -> Result<IntfResult, IntfError> {
-        self.write_u32(subnode, add, data as u32)
+        self.write_u32(subnode, add, data.to_bits())
     }
 
     pub fn read_f32(&mut self, subnode: u8, add: u16) -> Result<f32, IntfError> {
-        match self.internal_access(subnode, add, CFG_STD_READ) {
-            Ok(IntfResult::Data(value)) => {
-                Ok((value[CFG_DATA_IDX] as u32 | ((value[CFG_DATA_IDX + 1] as u32) << 16)) as f32)
-            }
-            Err(e) => Err(e),
-            _ => Err(IntfError::Interface),
-        }
+        Ok(f32::from_bits(self.read_u32(subnode, add)?))
     }
 
     pub fn write_f64(&mut self, subnode: u8, add: u16, data: f64) -> Result<IntfResult, IntfError> {
-        self.write_u64(subnode, add, data as u64)
+        self.write_u64(subnode, add, data.to_bits())
     }
 
     pub fn read_f64(&mut self, subnode: u8, add: u16) -> Result<f64, IntfError> {
-        match self.internal_access(subnode, add, CFG_STD_READ) {
-            Ok(IntfResult::Data(value)) => Ok((value[CFG_DATA_IDX] as u64
-                | ((value[CFG_DATA_IDX + 1] as u64) << 16)
-                | ((value[CFG_DATA_IDX + 2] as u64) << 32)
-                | ((value[CFG_DATA_IDX + 3] as u64) << 48))
-                as f64),
-            Err(e) => Err(e),
-            _ => Err(IntfError::Interface),
-        }
+        Ok(f64::from_bits(self.read_u64(subnode, add)?))
     }
 
     pub fn write_str(
@@ -291,8 +529,7 @@ where
                         if count == 5 {
                             self.frame.raw[COMMAND_IDX] = CFG_EXT_WRITE + (add << 4);
                             self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
-                            let built_frame = &self.frame.raw[..7];
-                            if self.interface.raw_write(built_frame).is_err() {
+                            if self.send(7).is_err() {
                                 return Err(IntfError::Interface);
                             }
                             count = CFG_DATA_IDX;
@@ -303,13 +540,18 @@ where
                     self.frame.raw[COMMAND_IDX] = CFG_STD_WRITE + (add << 4);
                     self.frame.raw[count] = 0u16;
                     self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
-                    let built_frame = &self.frame.raw[..7];
-                    match self.interface.raw_write(built_frame) {
+                    match self.send(7) {
                         Ok(_) => Ok(IntfResult::Success),
                         Err(e) => Err(e),
                     }
                 }
                 ExtMode::Extended => {
+                    // The payload is followed by a zero word, or by its CRC
+                    let words = size.div_ceil(2);
+                    if EXT_DATA_IDX + words >= N {
+                        return Err(IntfError::InvalidParameter);
+                    }
+
                     self.frame.raw[COMMAND_IDX] = CFG_EXT_WRITE + (add << 4);
                     self.frame.raw[CFG_DATA_IDX] = size as u16;
                     self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
@@ -326,14 +568,17 @@ where
                             char_value = char_list.next();
                         } else {
                             self.frame.raw[count] &= 0xffu16;
+                            count += 1;
                             break;
                         }
                         count += 1;
                     }
                     self.frame.raw[count] = 0u16;
-                    let built_frame = &self.frame.raw[..7 + size];
-
-                    match self.interface.raw_write(built_frame) {
+                    let mut frame_size = EXT_DATA_IDX + words + 1;
+                    if self.payload_crc {
+                        frame_size = seal_payload(&mut self.interface, &mut self.frame.raw, words)?;
+                    }
+                    match self.send(frame_size) {
                         Ok(_) => Ok(IntfResult::Success),
                         Err(e) => Err(e),
                     }
@@ -349,25 +594,14 @@ where
                     Ok(IntfResult::Data(value)) => value,
                     _ => return Err(IntfError::Interface),
                 };
-
-                let data_bytes = unsafe { data_words[2..].align_to::<u8>().1 };
+                self.check_ext_answer(&data_words[..])?;
 
                 if (data_words[1] & 0x1u16) != CFG_EXT_BIT {
-                    let string_result: String = data_bytes[..]
-                        .iter()
-                        .take_while(|&&u| u != 0)
-                        .map(|&u| std::char::from_u32(u as u32).unwrap())
-                        .collect();
-
-                    Ok(string_result)
+                    Ok(codec::decode_str(&data_words[CFG_DATA_IDX..CRC_IDX]))
                 } else {
-                    let string_result: String = data_bytes[10..]
-                        .iter()
-                        .take_while(|&&u| u != 0)
-                        .map(|&u| std::char::from_u32(u as u32).unwrap())
-                        .collect();
-
-                    Ok(string_result)
+                    let size = (data_words[CFG_DATA_IDX] as usize).div_ceil(2);
+                    let end = (EXT_DATA_IDX + size).min(N);
+                    Ok(codec::decode_str(&data_words[EXT_DATA_IDX..end]))
                 }
             }
             ExtMode::Segmented => {
@@ -378,14 +612,7 @@ where
                         _ => return Err(IntfError::Interface),
                     };
 
-                    let data_bytes = unsafe { data_words[2..6].align_to::<u8>().1 };
-                    let string_segment: String = data_bytes[..]
-                        .iter()
-                        .take_while(|&&u| u != 0)
-                        .map(|&u| std::char::from_u32(u as u32).unwrap())
-                        .collect();
-
-                    result.push_str(&string_segment);
+                    result.push_str(&codec::decode_str(&data_words[CFG_DATA_IDX..CRC_IDX]));
 
                     if (data_words[1] & 0x1u16) != CFG_EXT_BIT {
                         break;
@@ -396,28 +623,508 @@ where
         }
     }
 
-    pub fn into_cyclic(self) -> Main<Cyclic, INTF> {
-        Main {
-            frame: self.frame,
-            _state: Cyclic,
-            interface: self.interface,
-            ext_mode: self.ext_mode,
+    /// Reads the raw content of a register as a list of words. Standard
+    /// frames always return the four config words. Segmented transfers return
+    /// whole segments, so the last one may be padded with zeros
+    pub fn read_words(&mut self, subnode: u8, add: u16) -> Result<Vec<u16>, IntfError> {
+        match self.ext_mode {
+            ExtMode::Extended => {
+                let data = match self.internal_access(subnode, add, CFG_STD_READ) {
+                    Ok(IntfResult::Data(value)) => value,
+                    Err(e) => return Err(e),
+                    _ => return Err(IntfError::Interface),
+                };
+
+                if (data[COMMAND_IDX] & CFG_EXT_BIT) != CFG_EXT_BIT {
+                    return Ok(data[CFG_DATA_IDX..EXT_DATA_IDX - 1].to_vec());
+                }
+
+                let size = (data[CFG_DATA_IDX] as usize).div_ceil(2);
+                if EXT_DATA_IDX + size > N {
+                    return Err(IntfError::Interface);
+                }
+                self.check_ext_answer(&data[..])?;
+
+                Ok(data[EXT_DATA_IDX..EXT_DATA_IDX + size].to_vec())
+            }
+            ExtMode::Segmented => {
+                let mut result = Vec::new();
+                loop {
+                    let data = match self.internal_access(subnode, add, CFG_STD_READ) {
+                        Ok(IntfResult::Data(value)) => value,
+                        Err(e) => return Err(e),
+                        _ => return Err(IntfError::Interface),
+                    };
+
+                    result.extend_from_slice(&data[CFG_DATA_IDX..EXT_DATA_IDX - 1]);
+
+                    if (data[COMMAND_IDX] & CFG_EXT_BIT) != CFG_EXT_BIT {
+                        break;
+                    }
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    /// Writes a list of words to a register. Up to four words fit in a standard
+    /// frame, longer lists are sent according to the extended mode
+    pub fn write_words(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        data: &[u16],
+    ) -> Result<IntfResult, IntfError> {
+        let size = data.len();
+        if size <= MAX_STD_CFG_DATA / 2 {
+            self.frame.raw[CFG_DATA_IDX..EXT_DATA_IDX - 1].fill(0u16);
+            self.frame.raw[CFG_DATA_IDX..CFG_DATA_IDX + size].copy_from_slice(data);
+
+            return match self.internal_access(subnode, add, CFG_STD_WRITE) {
+                Ok(_) => Ok(IntfResult::Success),
+                Err(e) => Err(e),
+            };
+        }
+
+        match self.ext_mode {
+            ExtMode::Segmented => {
+                let mut segments = data.chunks(MAX_STD_CFG_DATA / 2).peekable();
+                while let Some(segment) = segments.next() {
+                    self.frame.raw[CFG_DATA_IDX..EXT_DATA_IDX - 1].fill(0u16);
+                    self.frame.raw[CFG_DATA_IDX..CFG_DATA_IDX + segment.len()]
+                        .copy_from_slice(segment);
+
+                    let cmd = if segments.peek().is_some() {
+                        CFG_EXT_WRITE
+                    } else {
+                        CFG_STD_WRITE
+                    };
+
+                    self.internal_access(subnode, add, cmd)?;
+                }
+                Ok(IntfResult::Success)
+            }
+            ExtMode::Extended => {
+                let crc_words = self.payload_crc as usize;
+                if EXT_DATA_IDX + size + crc_words > N {
+                    return Err(IntfError::InvalidParameter);
+                }
+
+                self.frame.raw[CFG_DATA_IDX] = (size * 2) as u16;
+                self.frame.raw[EXT_DATA_IDX..EXT_DATA_IDX + size].copy_from_slice(data);
+                let mut frame_size = EXT_DATA_IDX + size;
+                if self.payload_crc {
+                    frame_size = seal_payload(&mut self.interface, &mut self.frame.raw, size)?;
+                }
+
+                match self.internal_sized_access(subnode, add, CFG_EXT_WRITE, frame_size) {
+                    Ok(_) => Ok(IntfResult::Success),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Requests the node to move to cyclic state. If the node does not
+    /// acknowledge it, the Main is handed back in config state
+    #[allow(clippy::result_large_err)]
+    pub fn into_cyclic(
+        mut self,
+    ) -> Result<Main<Cyclic, INTF, N>, (Main<Config, INTF, N>, IntfError)> {
+        match self.state_change(TargetState::Cyclic) {
+            Ok(_) => Ok(self.transition(Cyclic)),
+            Err(e) => Err((self, e)),
         }
     }
 }
 
-/// These functions may be used on any Mcb in config State
-impl<INTF> Main<Cyclic, INTF>
+/// These functions may be used on any Mcb in cyclic State
+impl<INTF, const N: usize> Main<Cyclic, INTF, N>
 where
     INTF: PhysicalInterface,
 {
-    pub fn into_config(self) -> Main<Config, INTF> {
-        Main {
-            frame: self.frame,
-            _state: Config,
-            interface: self.interface,
-            ext_mode: self.ext_mode,
+    /// Exchanges a cyclic frame with the given subnode. The received cyclic
+    /// data has the same length as the sent one
+    ///
+    /// Once [`Main::set_link_loss_threshold`] cycles in a row failed, failures
+    /// are reported as [`IntfError::LinkLoss`] until a cycle succeeds again
+    pub fn cycle(&mut self, subnode: u8, data: &[u16]) -> Result<Vec<u16>, IntfError> {
+        let transaction = Transaction::begin("cycle", subnode, 0, CFG_IDLE);
+        let result = match self.exchange_cyclic(subnode, data) {
+            Err(IntfError::InvalidParameter) => Err(IntfError::InvalidParameter),
+            Err(e) => {
+                self.missed_cycles = self.missed_cycles.saturating_add(1);
+                match self.is_link_lost() {
+                    true => {
+                        if let Some(access) = self.access.as_mut() {
+                            access.result.get_or_insert(Err(IntfError::LinkLoss));
+                        }
+                        Err(IntfError::LinkLoss)
+                    }
+                    false => Err(e),
+                }
+            }
+            Ok(data) => {
+                self.missed_cycles = 0;
+                Ok(data)
+            }
+        };
+        transaction.end(&result);
+        result
+    }
+
+    /// Number of cycles failed in a row since the last successful one
+    pub fn missed_cycles(&self) -> u32 {
+        self.missed_cycles
+    }
+
+    pub fn is_link_lost(&self) -> bool {
+        self.link_loss_threshold > 0 && self.missed_cycles >= self.link_loss_threshold
+    }
+
+    fn exchange_cyclic(&mut self, subnode: u8, data: &[u16]) -> Result<Vec<u16>, IntfError> {
+        let size = data.len();
+        if CYC_DATA_IDX + size > N {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        match self
+            .outputs
+            .iter_mut()
+            .find(|(sent_to, _)| *sent_to == subnode)
+        {
+            Some((_, output)) => {
+                output.clear();
+                output.extend_from_slice(data);
+            }
+            None => self.outputs.push((subnode, data.to_vec())),
         }
+
+        let resync = self.resync.contains(&subnode);
+        let (command, words) = match &self.access {
+            Some(access) if !resync && access.subnode == subnode && access.result.is_none() => {
+                access.request()
+            }
+            _ => (CFG_IDLE, [0u16; 4]),
+        };
+
+        self.frame.raw[HEADER_IDX] = subnode as u16;
+        self.frame.raw[COMMAND_IDX] = command;
+        self.frame.raw[CFG_DATA_IDX..6].copy_from_slice(&words);
+        self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw[..6]);
+        self.frame.raw[CYC_DATA_IDX..CYC_DATA_IDX + size].copy_from_slice(data);
+
+        let data = match self.transfer_cyclic(subnode, size) {
+            Ok(data) => data,
+            Err(e) => {
+                // The node may have taken the segment, so sending it again
+                // could apply it twice
+                if command != CFG_IDLE {
+                    self.abort_access(subnode, e);
+                }
+                return Err(e);
+            }
+        };
+        if resync {
+            self.resync.retain(|resynced| *resynced != subnode);
+        }
+
+        if command != CFG_IDLE {
+            self.answer_access(&data[..]);
+        } else if (data[1] & 0xfu16) != CFG_IDLE {
+            self.stats.wrong_commands += 1;
+            return Err(IntfError::WrongCommand);
+        }
+
+        Ok(data[CYC_DATA_IDX..CYC_DATA_IDX + size].to_vec())
+    }
+
+    /// Sends the built cyclic frame and waits for the answer of the subnode
+    fn transfer_cyclic(&mut self, subnode: u8, size: usize) -> Result<Box<[u16; N]>, IntfError> {
+        let start = Instant::now();
+        match self.send(CYC_DATA_IDX + size) {
+            Ok(IntfResult::Success) => (),
+            _ => return Err(IntfError::Interface),
+        }
+
+        let data = self.receive()?;
+        self.stats.latency.record(start.elapsed());
+
+        if (data[0] & 0xfu16) != subnode as u16 {
+            self.stats.subnode_mismatches += 1;
+            return Err(IntfError::WrongSubnode);
+        }
+        Ok(data)
+    }
+
+    /// Completes the pending config access with the answer of the node
+    fn answer_access(&mut self, data: &[u16]) {
+        let Some(access) = self.access.as_mut() else {
+            return;
+        };
+
+        if (data[1] & 0xfffeu16) == (CFG_STD_ACK + (access.add << 4)) {
+            access.acknowledge(data);
+        } else if (data[1] & 0xf & !CFG_EXT_BIT) == CFG_ERR_BIT {
+            let code = (data[2] as u32) | ((data[3] as u32) << 16);
+            self.stats.access_error(code);
+            access.result = Some(Err(IntfError::Access(code)));
+        } else {
+            self.stats.wrong_commands += 1;
+            let subnode = access.subnode;
+            self.abort_access(subnode, IntfError::WrongCommand);
+        }
+    }
+
+    /// Ends the pending access with an error. The next frame to the subnode
+    /// is an idle one, which drops the segments the node took so far
+    fn abort_access(&mut self, subnode: u8, error: IntfError) {
+        if let Some(access) = self.access.as_mut() {
+            access.result.get_or_insert(Err(error));
+        }
+        if !self.resync.contains(&subnode) {
+            self.resync.push(subnode);
+        }
+    }
+
+    fn start_access(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        cmd: u16,
+        data: &[u16],
+    ) -> Result<IntfResult, IntfError> {
+        if add > MAX_ADDRESS {
+            return Err(IntfError::AddressOutOfIndex);
+        }
+        if self.access.is_some() {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        self.access = Some(CyclicAccess {
+            subnode,
+            add,
+            cmd,
+            data: data.to_vec(),
+            sent: 0,
+            received: Vec::new(),
+            result: None,
+        });
+        Ok(IntfResult::Success)
+    }
+
+    /// Starts reading the content of a register. The request is carried by
+    /// the config words of the next cycles with the subnode, without
+    /// disturbing their cyclic data. Contents longer than the four config
+    /// words are read in segments, one per cycle, whatever the extended mode.
+    /// A failed cycle aborts the access with its error, as the node may have
+    /// taken the segment. Fails if another access is pending
+    pub fn start_read(&mut self, subnode: u8, add: u16) -> Result<IntfResult, IntfError> {
+        self.start_access(subnode, add, CFG_STD_READ, &[])
+    }
+
+    /// Starts writing a list of words to a register. See
+    /// [`Main::start_read`]
+    pub fn start_write(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        data: &[u16],
+    ) -> Result<IntfResult, IntfError> {
+        self.start_access(subnode, add, CFG_STD_WRITE, data)
+    }
+
+    /// Completes an access over the next cycles with the subnode, sending
+    /// again the last cyclic data sent to it. A failed cycle carrying the
+    /// access aborts it. Failed idle cycles are repeated as set by
+    /// [`Main::set_retries`]
+    fn cyclic_access(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        cmd: u16,
+        data: &[u16],
+    ) -> Result<Vec<u16>, IntfError> {
+        self.start_access(subnode, add, cmd, data)?;
+        let output = self
+            .outputs
+            .iter()
+            .find(|(sent_to, _)| *sent_to == subnode)
+            .map(|(_, output)| output.clone())
+            .unwrap_or_default();
+
+        let mut failures = 0;
+        loop {
+            if let Err(e) = self.cycle(subnode, &output) {
+                failures += 1;
+                if failures > self.retries || matches!(e, IntfError::LinkLoss) {
+                    self.access = None;
+                    return Err(e);
+                }
+            }
+
+            if let Some(result) = self.access_result() {
+                return result;
+            }
+        }
+    }
+
+    /// Whether an access was started and its result was not taken yet
+    pub fn has_pending_access(&self) -> bool {
+        self.access.is_some()
+    }
+
+    /// Takes the result of the pending access once it completed: the words
+    /// read, or no words for a write. Segmented reads return whole
+    /// segments, so the last one may be padded with zeros
+    pub fn access_result(&mut self) -> Option<Result<Vec<u16>, IntfError>> {
+        match self.access.as_ref().map(|access| access.result.is_some()) {
+            Some(true) => self.access.take().and_then(|access| access.result),
+            _ => None,
+        }
+    }
+
+    /// Requests the node to move back to config state. If the node does not
+    /// acknowledge it, the Main is handed back in cyclic state
+    #[allow(clippy::result_large_err)]
+    pub fn into_config(
+        mut self,
+    ) -> Result<Main<Config, INTF, N>, (Main<Cyclic, INTF, N>, IntfError)> {
+        match self.state_change(TargetState::Config) {
+            Ok(_) => Ok(self.transition(Config)),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
+
+/// Register accesses in cyclic state. Each one takes as many cycles with the
+/// subnode as needed to carry it in their config words, see
+/// [`Main::start_read`]. These cycles send again the last cyclic data sent to
+/// the subnode, and the cyclic data they receive is dropped
+impl<INTF, const N: usize> Main<Cyclic, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Little endian value of the first four words of a register
+    fn read_long(&mut self, subnode: u8, add: u16) -> Result<u64, IntfError> {
+        let words = self.read_words(subnode, add)?;
+        Ok(words
+            .iter()
+            .take(4)
+            .enumerate()
+            .fold(0u64, |long, (index, word)| {
+                long | (*word as u64) << (16 * index)
+            }))
+    }
+
+    pub fn write_u8(&mut self, subnode: u8, add: u16, data: u8) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::U8(data))
+    }
+
+    pub fn read_u8(&mut self, subnode: u8, add: u16) -> Result<u8, IntfError> {
+        Ok(self.read_long(subnode, add)? as u8)
+    }
+
+    pub fn write_i8(&mut self, subnode: u8, add: u16, data: i8) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::I8(data))
+    }
+
+    pub fn read_i8(&mut self, subnode: u8, add: u16) -> Result<i8, IntfError> {
+        Ok(self.read_long(subnode, add)? as i8)
+    }
+
+    pub fn write_u16(&mut self, subnode: u8, add: u16, data: u16) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::U16(data))
+    }
+
+    pub fn read_u16(&mut self, subnode: u8, add: u16) -> Result<u16, IntfError> {
+        Ok(self.read_long(subnode, add)? as u16)
+    }
+
+    pub fn write_i16(&mut self, subnode: u8, add: u16, data: i16) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::I16(data))
+    }
+
+    pub fn read_i16(&mut self, subnode: u8, add: u16) -> Result<i16, IntfError> {
+        Ok(self.read_long(subnode, add)? as i16)
+    }
+
+    pub fn write_u32(&mut self, subnode: u8, add: u16, data: u32) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::U32(data))
+    }
+
+    pub fn read_u32(&mut self, subnode: u8, add: u16) -> Result<u32, IntfError> {
+        Ok(self.read_long(subnode, add)? as u32)
+    }
+
+    pub fn write_i32(&mut self, subnode: u8, add: u16, data: i32) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::I32(data))
+    }
+
+    pub fn read_i32(&mut self, subnode: u8, add: u16) -> Result<i32, IntfError> {
+        Ok(self.read_long(subnode, add)? as i32)
+    }
+
+    pub fn write_u64(&mut self, subnode: u8, add: u16, data: u64) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::U64(data))
+    }
+
+    pub fn read_u64(&mut self, subnode: u8, add: u16) -> Result<u64, IntfError> {
+        self.read_long(subnode, add)
+    }
+
+    pub fn write_i64(&mut self, subnode: u8, add: u16, data: i64) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::I64(data))
+    }
+
+    pub fn read_i64(&mut self, subnode: u8, add: u16) -> Result<i64, IntfError> {
+        Ok(self.read_long(subnode, add)? as i64)
+    }
+
+    pub fn write_f32(&mut self, subnode: u8, add: u16, data: f32) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::F32(data))
+    }
+
+    pub fn read_f32(&mut self, subnode: u8, add: u16) -> Result<f32, IntfError> {
+        Ok(f32::from_bits(self.read_long(subnode, add)? as u32))
+    }
+
+    pub fn write_f64(&mut self, subnode: u8, add: u16, data: f64) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::F64(data))
+    }
+
+    pub fn read_f64(&mut self, subnode: u8, add: u16) -> Result<f64, IntfError> {
+        Ok(f64::from_bits(self.read_long(subnode, add)?))
+    }
+
+    /// Writes a string, in segments if it does not fit in the config words
+    pub fn write_str(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        data: &str,
+    ) -> Result<IntfResult, IntfError> {
+        self.write_value(subnode, add, &Value::Str(data.to_string()))
+    }
+
+    pub fn read_str(&mut self, subnode: u8, add: u16) -> Result<String, IntfError> {
+        Ok(codec::decode_str(&self.read_words(subnode, add)?))
+    }
+
+    /// Reads the raw content of a register as a list of words, see
+    /// [`Main::access_result`]
+    pub fn read_words(&mut self, subnode: u8, add: u16) -> Result<Vec<u16>, IntfError> {
+        self.cyclic_access(subnode, add, CFG_STD_READ, &[])
+    }
+
+    pub fn write_words(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        data: &[u16],
+    ) -> Result<IntfResult, IntfError> {
+        self.cyclic_access(subnode, add, CFG_STD_WRITE, data)?;
+        Ok(IntfResult::Success)
     }
 }
 
@@ -426,15 +1133,32 @@ pub fn create_main_mcb<INTF: PhysicalInterface>(
     mode: ExtMode,
     subnode: u8,
 ) -> Main<Init, INTF> {
+    create_sized_main_mcb(interface, mode, subnode)
+}
+
+/// Creates a Main using frames of `N` words, e.g. shorter than
+/// [`MAX_FRAME_SIZE`] on devices with little memory or longer on links with
+/// large cyclic mappings. Cyclic data and extended payloads not fitting in the
+/// frame are rejected with [`IntfError::InvalidParameter`]
+pub fn create_sized_main_mcb<INTF: PhysicalInterface, const N: usize>(
+    interface: Option<INTF>,
+    mode: ExtMode,
+    subnode: u8,
+) -> Main<Init, INTF, N> {
     let interface_in = interface.unwrap();
     Main {
-        frame: Frame {
-            _address: 0u16,
-            subnode,
-            raw: [0u16; MAX_FRAME_SIZE],
-        },
+        frame: Frame::new(subnode),
         _state: Init,
         interface: interface_in,
         ext_mode: mode,
+        payload_crc: false,
+        retries: 0,
+        stats: Statistics::default(),
+        link_loss_threshold: 0,
+        response_timeout: None,
+        missed_cycles: 0,
+        access: None,
+        outputs: Vec::new(),
+        resync: Vec::new(),
     }
 }
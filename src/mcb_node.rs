@@ -1,56 +1,261 @@
+use crate::frame::Frame;
+use crate::stats::Statistics;
+use crate::trace::Transaction;
+use crate::watchdog::{Watchdog, WatchdogState};
 use crate::*;
+
+use std::time::Duration;
+
 #[derive(Debug)]
 pub enum CommandType {
     Read,
     Write,
     ExtRead,
     ExtWrite,
-    StateChange,
+    /// Request to move to the given state. Accept it with
+    /// [`Node::into_cyclic`] or [`Node::into_config`], or reject it with
+    /// [`Node::reject_state_change`]
+    StateChange(TargetState),
+    Idle,
 }
 pub struct Request {
     pub subnode: u8,
     pub address: u16,
     pub command: CommandType,
-    data_value: [u16; MAX_FRAME_SIZE],
+    data_value: Box<[u16]>,
+    data_range: core::ops::Range<usize>,
+    /// Config data of a write in cyclic state, gathered from its segments
+    config: Vec<u16>,
 }
-pub struct Node<STATE, INTERFACE: PhysicalInterface> {
-    frame: Frame,
+/// `N` is the size of the frames in words, see [`create_sized_node_mcb`]
+pub struct Node<STATE, INTERFACE: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    frame: Frame<N>,
     _state: STATE,
     interface: INTERFACE,
     ext_mode: ExtMode,
+    subnodes: Vec<u8>,
+    payload_crc: bool,
+    stats: Statistics,
+    watchdog: Watchdog,
+    config_answer: Option<(u16, [u16; 4])>,
+    /// Segments of a write in cyclic state received so far
+    segments: Vec<u16>,
+    /// Address and segments of a read answer in cyclic state still to send
+    outgoing: Option<(u16, Vec<u16>)>,
 }
 
 /// These functions may be used on any Mcb struct
-impl<STAT, INTF> Node<STAT, INTF>
+impl<STAT, INTF, const N: usize> Node<STAT, INTF, N>
 where
     INTF: PhysicalInterface,
 {
-    pub fn init(self) -> Node<Config, INTF> {
+    pub fn init(self) -> Node<Config, INTF, N> {
+        self.transition(Config)
+    }
+
+    /// Serves the given subnode too. Requests carry the subnode they were
+    /// addressed to and their answers are sent from it
+    pub fn add_subnode(&mut self, subnode: u8) {
+        if !self.subnodes.contains(&subnode) {
+            self.subnodes.push(subnode);
+        }
+    }
+
+    /// Protects the payload of extended frames with a trailing CRC. See
+    /// [`crate::mcb_main::Main::set_payload_crc`]
+    pub fn set_payload_crc(&mut self, enabled: bool) {
+        self.payload_crc = enabled;
+    }
+
+    pub fn has_payload_crc(&self) -> bool {
+        self.payload_crc
+    }
+
+    /// Size of the frames in words
+    pub fn frame_size(&self) -> usize {
+        N
+    }
+
+    /// Largest payload, in words, of an extended answer
+    pub fn max_extended_words(&self) -> usize {
+        N.saturating_sub(EXT_DATA_IDX + self.payload_crc as usize)
+    }
+
+    /// Largest cyclic data, in words, exchanged in a cycle
+    pub fn max_cyclic_words(&self) -> usize {
+        N - CYC_DATA_IDX
+    }
+
+    /// Maximum time between two cyclic frames. Once it elapses in cyclic
+    /// state, the watchdog expires, see [`Node::watchdog`]. Disabled by
+    /// default
+    ///
+    /// The watchdog has no timer of its own: expiry is only checked by
+    /// [`Node::read`], [`Node::listen`] and [`Node::watchdog`]. If the
+    /// `raw_read` of the interface blocks, the application must call
+    /// [`Node::watchdog`] from its own loop or timer, otherwise the drive is
+    /// not stopped while the Main is silent
+    pub fn set_cycle_timeout(&mut self, timeout: Option<Duration>) {
+        self.watchdog.set_timeout(timeout);
+    }
+
+    /// Called once each time the watchdog expires, e.g. to disable the power
+    /// stage. It runs from the call that noticed the expiry, see
+    /// [`Node::set_cycle_timeout`]
+    pub fn on_cycle_timeout<F>(&mut self, callback: F)
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.watchdog.set_callback(Box::new(callback));
+    }
+
+    /// Subnodes served by the Node
+    pub fn subnodes(&self) -> &[u8] {
+        &self.subnodes
+    }
+
+    /// Checks that the header addresses a served subnode and answers from it
+    fn select_subnode(&mut self, header: u16) -> Result<(), IntfError> {
+        let subnode = (header & 0xfu16) as u8;
+        if !self.subnodes.contains(&subnode) {
+            self.stats.subnode_mismatches += 1;
+            return Err(IntfError::WrongSubnode);
+        }
+
+        self.frame.subnode = subnode;
+        Ok(())
+    }
+
+    /// Writes the first `size` words of the frame to the interface
+    fn send(&mut self, size: usize) -> Result<IntfResult, IntfError> {
+        let result = self.interface.raw_write(&self.frame.raw[..size]);
+        if result.is_ok() {
+            self.stats.frames_sent += 1;
+        }
+        result
+    }
+
+    fn receive(&mut self) -> Result<Box<[u16; N]>, IntfError> {
+        match self.interface.raw_read() {
+            Ok(IntfResult::Data(value)) => {
+                self.stats.frames_received += 1;
+                let Ok(frame) = fit_frame(value) else {
+                    self.stats.oversized_frames += 1;
+                    return Err(IntfError::InvalidParameter);
+                };
+                Ok(frame)
+            }
+            Err(IntfError::Timeout) => {
+                self.stats.timeouts += 1;
+                Err(IntfError::Interface)
+            }
+            _ => Err(IntfError::Interface),
+        }
+    }
+
+    fn check_crc(&mut self, data: &[u16]) -> Result<(), IntfError> {
+        if data[6] != self.interface.crc_checksum(&data[..6]) {
+            self.stats.crc_errors += 1;
+            return Err(IntfError::Crc);
+        }
+        Ok(())
+    }
+
+    pub fn get_data_u8(&self, request: &Request) -> u8 {
+        request.data_value[CFG_DATA_IDX] as u8
+    }
+
+    pub fn get_data_i8(&self, request: &Request) -> i8 {
+        self.get_data_u8(request) as i8
+    }
+
+    pub fn get_data_u16(&self, request: &Request) -> u16 {
+        request.data_value[CFG_DATA_IDX]
+    }
+
+    pub fn get_data_i16(&self, request: &Request) -> i16 {
+        self.get_data_u16(request) as i16
+    }
+
+    pub fn get_data_u32(&self, request: &Request) -> u32 {
+        request.data_value[CFG_DATA_IDX] as u32
+            | ((request.data_value[CFG_DATA_IDX + 1] as u32) << 16)
+    }
+
+    pub fn get_data_i32(&self, request: &Request) -> i32 {
+        self.get_data_u32(request) as i32
+    }
+
+    pub fn get_data_u64(&self, request: &Request) -> u64 {
+        request.data_value[CFG_DATA_IDX] as u64
+            | ((request.data_value[CFG_DATA_IDX + 1] as u64) << 16)
+            | ((request.data_value[CFG_DATA_IDX + 2] as u64) << 32)
+            | ((request.data_value[CFG_DATA_IDX + 3] as u64) << 48)
+    }
+
+    pub fn get_data_i64(&self, request: &Request) -> i64 {
+        self.get_data_u64(request) as i64
+    }
+
+    pub fn get_data_f32(&self, request: &Request) -> f32 {
+        f32::from_bits(self.get_data_u32(request))
+    }
+
+    pub fn get_data_f64(&self, request: &Request) -> f64 {
+        f64::from_bits(self.get_data_u64(request))
+    }
+
+    /// Snapshot of the traffic counters
+    pub fn statistics(&self) -> Statistics {
+        self.stats.clone()
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.stats = Statistics::default();
+    }
+
+    fn write_internal(&mut self, add: u16, cmd: u16) -> Result<IntfResult, IntfError> {
+        self.frame.raw[HEADER_IDX] = self.frame.subnode as u16;
+        self.frame.raw[COMMAND_IDX] = cmd + (add << 4);
+        self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
+
+        self.send(7)
+    }
+
+    /// Rejects a state change request with the given error code. The Node
+    /// stays in its current state
+    pub fn reject_state_change(&mut self, err: u32) -> Result<IntfResult, IntfError> {
+        self.stats.access_error(err);
+        self.frame.raw[CFG_DATA_IDX] = err as u16;
+        self.frame.raw[CFG_DATA_IDX + 1] = (err >> 16) as u16;
+
+        self.write_internal(0, CFG_ERR_BIT)
+    }
+
+    fn transition<NEXT>(self, state: NEXT) -> Node<NEXT, INTF, N> {
         Node {
             frame: self.frame,
-            _state: Config,
+            _state: state,
             interface: self.interface,
             ext_mode: self.ext_mode,
+            subnodes: self.subnodes,
+            payload_crc: self.payload_crc,
+            stats: self.stats,
+            watchdog: self.watchdog,
+            config_answer: None,
+            segments: Vec::new(),
+            outgoing: None,
         }
     }
 }
 
 /// These functions may be used on any Mcb in config State
-impl<INTF> Node<Config, INTF>
+impl<INTF, const N: usize> Node<Config, INTF, N>
 where
     INTF: PhysicalInterface,
 {
-    fn write_internal(&mut self, add: u16, cmd: u16) -> Result<IntfResult, IntfError> {
-        self.frame.raw[HEADER_IDX] = self.frame.subnode as u16;
-        self.frame.raw[COMMAND_IDX] = cmd + (add << 4);
-        self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
-
-        let built_frame = &self.frame.raw[..7];
-
-        self.interface.raw_write(built_frame)
-    }
-
     pub fn error(&mut self, addcmd: u16, err: u32) -> Result<IntfResult, IntfError> {
+        self.stats.access_error(err);
         self.frame.raw[CFG_DATA_IDX] = err as u16;
         self.frame.raw[CFG_DATA_IDX + 1] = (err >> 16) as u16;
 
@@ -106,11 +311,11 @@ where
     }
 
     pub fn write_f32(&mut self, add: u16, data: f32) -> Result<IntfResult, IntfError> {
-        self.write_u32(add, data as u32)
+        self.write_u32(add, data.to_bits())
     }
 
     pub fn write_f64(&mut self, add: u16, data: f64) -> Result<IntfResult, IntfError> {
-        self.write_u64(add, data as u64)
+        self.write_u64(add, data.to_bits())
     }
 
     pub fn write_str(&mut self, add: u16, data: &str) -> Result<IntfResult, IntfError> {
@@ -156,26 +361,22 @@ where
                         if count == 5 {
                             self.frame.raw[COMMAND_IDX] = CFG_EXT_ACK + (add << 4);
                             self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
-                            let built_frame = &self.frame.raw[..7];
-                            if self.interface.raw_write(built_frame).is_err() {
+                            if self.send(7).is_err() {
                                 return Err(IntfError::Interface);
                             }
                             count = CFG_DATA_IDX;
 
-                            let data = match self.interface.raw_read() {
-                                Ok(IntfResult::Data(value)) => value,
-                                _ => return Err(IntfError::Interface),
-                            };
+                            let data = self.receive()?;
 
                             if (data[0] & 0xfu16) != self.frame.subnode as u16 {
+                                self.stats.subnode_mismatches += 1;
                                 return Err(IntfError::WrongSubnode);
                             }
 
-                            if data[6] != self.interface.crc_checksum(&data[..6]) {
-                                return Err(IntfError::Crc);
-                            }
+                            self.check_crc(&data[..])?;
 
                             if (data[1] & 0xfu16) != CFG_STD_READ {
+                                self.stats.wrong_commands += 1;
                                 return Err(IntfError::WrongCommand);
                             }
                         } else {
@@ -185,10 +386,15 @@ where
                     self.frame.raw[COMMAND_IDX] = CFG_STD_ACK + (add << 4);
                     self.frame.raw[count] = 0u16;
                     self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
-                    let built_frame = &self.frame.raw[..7];
-                    self.interface.raw_write(built_frame)
+                    self.send(7)
                 }
                 ExtMode::Extended => {
+                    // The payload is followed by a zero word, or by its CRC
+                    let words = size.div_ceil(2);
+                    if EXT_DATA_IDX + words >= N {
+                        return Err(IntfError::InvalidParameter);
+                    }
+
                     self.frame.raw[COMMAND_IDX] = CFG_EXT_ACK + (add << 4);
                     self.frame.raw[CFG_DATA_IDX] = size as u16;
                     self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
@@ -204,38 +410,123 @@ where
                             char_value = char_list.next();
                         } else {
                             self.frame.raw[count] &= 0xffu16;
+                            count += 1;
                             break;
                         }
                         count += 1;
                     }
                     self.frame.raw[count] = 0u16;
-                    let built_frame = &self.frame.raw[..7 + size];
+                    let mut frame_size = EXT_DATA_IDX + words + 1;
+                    if self.payload_crc {
+                        frame_size = seal_payload(&mut self.interface, &mut self.frame.raw, words)?;
+                    }
+                    self.send(frame_size)
+                }
+            }
+        }
+    }
+
+    /// Answers a read request with a list of words. Up to four words fit in a
+    /// standard frame, longer lists are sent according to the extended mode
+    pub fn write_words(&mut self, add: u16, data: &[u16]) -> Result<IntfResult, IntfError> {
+        let size = data.len();
+        if size <= MAX_STD_CFG_DATA / 2 {
+            self.frame.raw[CFG_DATA_IDX..EXT_DATA_IDX - 1].fill(0u16);
+            self.frame.raw[CFG_DATA_IDX..CFG_DATA_IDX + size].copy_from_slice(data);
+            return self.write_internal(add, CFG_STD_ACK);
+        }
+
+        match self.ext_mode {
+            ExtMode::Segmented => {
+                let mut segments = data.chunks(MAX_STD_CFG_DATA / 2).peekable();
+                while let Some(segment) = segments.next() {
+                    self.frame.raw[CFG_DATA_IDX..EXT_DATA_IDX - 1].fill(0u16);
+                    self.frame.raw[CFG_DATA_IDX..CFG_DATA_IDX + segment.len()]
+                        .copy_from_slice(segment);
+
+                    if segments.peek().is_none() {
+                        break;
+                    }
+
+                    if self.write_internal(add, CFG_EXT_ACK).is_err() {
+                        return Err(IntfError::Interface);
+                    }
+
+                    let data = self.receive()?;
+
+                    if (data[0] & 0xfu16) != self.frame.subnode as u16 {
+                        self.stats.subnode_mismatches += 1;
+                        return Err(IntfError::WrongSubnode);
+                    }
+
+                    self.check_crc(&data[..])?;
 
-                    self.interface.raw_write(built_frame)
+                    if (data[1] & 0xfu16) != CFG_STD_READ {
+                        self.stats.wrong_commands += 1;
+                        return Err(IntfError::WrongCommand);
+                    }
                 }
+                self.write_internal(add, CFG_STD_ACK)
+            }
+            ExtMode::Extended => {
+                let crc_words = self.payload_crc as usize;
+                if EXT_DATA_IDX + size + crc_words > N {
+                    return Err(IntfError::InvalidParameter);
+                }
+
+                self.frame.raw[HEADER_IDX] = self.frame.subnode as u16;
+                self.frame.raw[COMMAND_IDX] = CFG_EXT_ACK + (add << 4);
+                self.frame.raw[CFG_DATA_IDX] = (size * 2) as u16;
+                self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw);
+                self.frame.raw[EXT_DATA_IDX..EXT_DATA_IDX + size].copy_from_slice(data);
+                let mut frame_size = EXT_DATA_IDX + size;
+                if self.payload_crc {
+                    frame_size = seal_payload(&mut self.interface, &mut self.frame.raw, size)?;
+                }
+
+                self.send(frame_size)
             }
         }
     }
 
     pub fn read(&mut self) -> Result<Request, IntfError> {
-        let mut data = match self.interface.raw_read() {
-            Ok(IntfResult::Data(value)) => value,
-            _ => return Err(IntfError::Interface),
-        };
+        let data = self.receive()?;
+        let transaction = begin_read(&data[..]);
+        let request = self.decode(data);
+        transaction.end(&request);
+        request
+    }
 
-        if (data[0] & 0xfu16) != self.frame.subnode as u16 {
-            return Err(IntfError::WrongSubnode);
-        }
+    fn decode(&mut self, mut data: Box<[u16; N]>) -> Result<Request, IntfError> {
+        self.check_crc(&data[..])?;
 
-        if data[6] != self.interface.crc_checksum(&data[..6]) {
-            return Err(IntfError::Crc);
-        }
+        self.select_subnode(data[HEADER_IDX])?;
+
+        let mut data_range = CFG_DATA_IDX..EXT_DATA_IDX - 1;
 
         let command = match data[1] & 0xfu16 {
             CFG_STD_READ => CommandType::Read,
             CFG_STD_WRITE => CommandType::Write,
             CFG_EXT_READ => CommandType::ExtRead,
+            CFG_STATE_CHANGE => match TargetState::from_code(data[CFG_DATA_IDX]) {
+                Some(target) => CommandType::StateChange(target),
+                None => return Err(IntfError::InvalidParameter),
+            },
             CFG_EXT_WRITE => {
+                if let ExtMode::Extended = self.ext_mode {
+                    let size = (data[CFG_DATA_IDX] as usize).div_ceil(2);
+                    if EXT_DATA_IDX + size > N {
+                        return Err(IntfError::InvalidParameter);
+                    }
+                    if self.payload_crc {
+                        if let Err(e) = check_payload(&mut self.interface, &data[..]) {
+                            self.stats.payload_crc_errors += 1;
+                            return Err(e);
+                        }
+                    }
+                    data_range = EXT_DATA_IDX..EXT_DATA_IDX + size;
+                }
+
                 if let ExtMode::Segmented = self.ext_mode {
                     let mut count = 6;
                     loop {
@@ -249,13 +540,12 @@ where
                             is_ready = self.listen();
                         }
 
-                        let data_segment = match self.interface.raw_read() {
-                            Ok(IntfResult::Data(value)) => value,
-                            _ => return Err(IntfError::Interface),
-                        };
+                        let data_segment = self.receive()?;
 
-                        if data_segment[6] != self.interface.crc_checksum(&data[..6]) {
-                            return Err(IntfError::Crc);
+                        self.check_crc(&data_segment[..])?;
+
+                        if count + 4 > N {
+                            return Err(IntfError::InvalidParameter);
                         }
 
                         data[count..count + 4].copy_from_slice(&data_segment[2..6]);
@@ -266,102 +556,347 @@ where
                             break;
                         }
                     }
+                    data_range = CFG_DATA_IDX..count;
                 }
                 CommandType::ExtWrite
             }
-            _ => return Err(IntfError::WrongCommand),
+            _ => {
+                self.stats.wrong_commands += 1;
+                return Err(IntfError::WrongCommand);
+            }
         };
 
         Ok(Request {
             subnode: data[HEADER_IDX] as u8 & 0xfu8,
             address: data[COMMAND_IDX] >> 4,
             command,
-            data_value: *data,
+            data_value: data,
+            data_range,
+            config: Vec::new(),
         })
     }
 
-    pub fn get_data_u8(&self, request: &Request) -> u8 {
-        request.data_value[CFG_DATA_IDX] as u8
+    /// Raw content of a write request. Extended and segmented transfers
+    /// return the whole payload
+    pub fn get_data_words<'a>(&self, request: &'a Request) -> &'a [u16] {
+        &request.data_value[request.data_range.clone()]
     }
 
-    pub fn get_data_i8(&self, request: &Request) -> i8 {
-        self.get_data_u8(request) as i8
+    /// String content of a write request. Extended and segmented transfers
+    /// return the whole payload
+    pub fn get_data_str(&self, request: &Request) -> String {
+        codec::decode_str(self.get_data_words(request))
     }
 
-    pub fn get_data_u16(&self, request: &Request) -> u16 {
-        request.data_value[CFG_DATA_IDX]
+    pub fn listen(&mut self) -> Result<IntfResult, IntfError> {
+        self.interface.is_data2read()
     }
 
-    pub fn get_data_i16(&self, request: &Request) -> i16 {
-        self.get_data_u16(request) as i16
+    /// Acknowledges a state change request and moves to cyclic state. If the
+    /// request does not ask for cyclic state or the acknowledge cannot be
+    /// sent, the Node is handed back in config state
+    #[allow(clippy::result_large_err)]
+    pub fn into_cyclic(
+        mut self,
+        request: &Request,
+    ) -> Result<Node<Cyclic, INTF, N>, (Node<Config, INTF, N>, IntfError)> {
+        if !matches!(
+            request.command,
+            CommandType::StateChange(TargetState::Cyclic)
+        ) {
+            return Err((self, IntfError::WrongCommand));
+        }
+
+        match self.write_internal(0, CFG_STD_ACK) {
+            Ok(_) => {
+                self.watchdog.feed();
+                Ok(self.transition(Cyclic))
+            }
+            Err(e) => Err((self, e)),
+        }
     }
+}
 
-    pub fn get_data_u32(&self, request: &Request) -> u32 {
-        request.data_value[CFG_DATA_IDX] as u32
-            | ((request.data_value[CFG_DATA_IDX + 1] as u32) << 16)
+/// These functions may be used on any Mcb in cyclic State
+impl<INTF, const N: usize> Node<Cyclic, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Reads a cyclic frame. Use [`Node::get_cyclic_data`] to access its
+    /// cyclic data and answer it with [`Node::write_cyclic`]. Frames carrying
+    /// a config access are read as [`CommandType::Read`] or
+    /// [`CommandType::Write`], see [`Node::answer_config`]. Valid frames
+    /// feed the watchdog. Once it expired, failures are reported as
+    /// [`IntfError::LinkLoss`]
+    pub fn read(&mut self) -> Result<Request, IntfError> {
+        let request = self.receive().and_then(|data| {
+            let transaction = begin_read(&data[..]);
+            let request = self.decode(data);
+            transaction.end(&request);
+            request
+        });
+
+        match request {
+            Ok(request) => {
+                self.watchdog.feed();
+                Ok(request)
+            }
+            Err(e) => match self.watchdog() {
+                WatchdogState::Expired => Err(IntfError::LinkLoss),
+                _ => Err(e),
+            },
+        }
     }
 
-    pub fn get_data_i32(&self, request: &Request) -> i32 {
-        self.get_data_u32(request) as i32
+    fn decode(&mut self, data: Box<[u16; N]>) -> Result<Request, IntfError> {
+        self.check_crc(&data[..])?;
+
+        self.select_subnode(data[HEADER_IDX])?;
+
+        let address = data[COMMAND_IDX] >> 4;
+        let cmd = data[COMMAND_IDX] & 0xfu16;
+        if cmd != CFG_STD_WRITE && cmd != CFG_EXT_WRITE {
+            self.segments.clear();
+        }
+        let continued = match self.outgoing.take() {
+            Some((add, rest)) if cmd == CFG_STD_READ && add == address => Some((add, rest)),
+            _ => None,
+        };
+
+        let mut config = Vec::new();
+        let command = match cmd {
+            CFG_IDLE => CommandType::Idle,
+            CFG_STD_READ => match continued {
+                Some(outgoing) => {
+                    self.outgoing = Some(outgoing);
+                    self.stage_segment();
+                    CommandType::Idle
+                }
+                None => CommandType::Read,
+            },
+            CFG_EXT_WRITE => {
+                self.segments
+                    .extend_from_slice(&data[CFG_DATA_IDX..CRC_IDX]);
+                self.config_answer = Some((CFG_STD_ACK + (address << 4), [0u16; 4]));
+                CommandType::Idle
+            }
+            CFG_STD_WRITE => {
+                self.segments
+                    .extend_from_slice(&data[CFG_DATA_IDX..CRC_IDX]);
+                config = core::mem::take(&mut self.segments);
+                CommandType::Write
+            }
+            CFG_STATE_CHANGE => match TargetState::from_code(data[CFG_DATA_IDX]) {
+                Some(target) => CommandType::StateChange(target),
+                None => return Err(IntfError::InvalidParameter),
+            },
+            _ => {
+                self.stats.wrong_commands += 1;
+                return Err(IntfError::WrongCommand);
+            }
+        };
+
+        Ok(Request {
+            subnode: data[HEADER_IDX] as u8 & 0xfu8,
+            address,
+            command,
+            data_value: data,
+            data_range: CYC_DATA_IDX..N,
+            config,
+        })
     }
 
-    pub fn get_data_u64(&self, request: &Request) -> u64 {
-        request.data_value[CFG_DATA_IDX] as u64
-            | ((request.data_value[CFG_DATA_IDX + 1] as u64) << 16)
-            | ((request.data_value[CFG_DATA_IDX + 2] as u64) << 32)
-            | ((request.data_value[CFG_DATA_IDX + 3] as u64) << 48)
+    /// First `size` words of cyclic data of a request
+    pub fn get_cyclic_data<'a>(&self, request: &'a Request, size: usize) -> &'a [u16] {
+        let size = size.min(request.data_range.len());
+        &request.data_value[request.data_range.start..request.data_range.start + size]
     }
 
-    pub fn get_data_i64(&self, request: &Request) -> i64 {
-        self.get_data_u64(request) as i64
+    /// Content of a write request, whole even if it was sent in segments
+    pub fn get_data_words<'a>(&self, request: &'a Request) -> &'a [u16] {
+        &request.config
     }
 
-    pub fn get_data_f32(&self, request: &Request) -> f32 {
-        self.get_data_u32(request) as f32
+    /// String content of a write request
+    pub fn get_data_str(&self, request: &Request) -> String {
+        codec::decode_str(self.get_data_words(request))
     }
 
-    pub fn get_data_f64(&self, request: &Request) -> f64 {
-        self.get_data_u64(request) as f64
+    /// Answers the config access of the last cyclic frame. The answer is sent
+    /// with the next [`Node::write_cyclic`]. Data longer than the four config
+    /// words is sent in segments, each one answering a further read request
+    /// of the Main
+    pub fn answer_config(&mut self, add: u16, data: &[u16]) -> Result<IntfResult, IntfError> {
+        self.outgoing = Some((add, data.to_vec()));
+        self.stage_segment();
+        Ok(IntfResult::Success)
     }
 
-    pub fn get_data_str(&self, request: &Request) -> String {
-        let data_bytes = unsafe { request.data_value[2..].align_to::<u8>().1 };
-        let result: String = data_bytes
-            .iter()
-            .take_while(|&&u| u != 0)
-            .map(|&u| std::char::from_u32(u as u32).unwrap())
-            .collect();
+    /// Stages the next segment of the outgoing answer
+    fn stage_segment(&mut self) {
+        let Some((add, mut data)) = self.outgoing.take() else {
+            return;
+        };
 
-        result
+        let size = data.len().min(MAX_STD_CFG_DATA / 2);
+        let mut words = [0u16; 4];
+        words[..size].copy_from_slice(&data[..size]);
+        let rest = data.split_off(size);
+        let mut command = CFG_STD_ACK + (add << 4);
+        if !rest.is_empty() {
+            command |= CFG_EXT_BIT;
+            self.outgoing = Some((add, rest));
+        }
+        self.config_answer = Some((command, words));
+    }
+
+    /// Rejects the config access of the last cyclic frame with the given
+    /// error code. The error is sent with the next [`Node::write_cyclic`]
+    pub fn reject_config(&mut self, err: u32) -> Result<IntfResult, IntfError> {
+        self.stats.access_error(err);
+        self.outgoing = None;
+        self.config_answer = Some((CFG_ERR_BIT, [err as u16, (err >> 16) as u16, 0, 0]));
+        Ok(IntfResult::Success)
+    }
+
+    /// Answers a cyclic frame with the given cyclic data, along with the
+    /// config answer if any
+    pub fn write_cyclic(&mut self, data: &[u16]) -> Result<IntfResult, IntfError> {
+        let size = data.len();
+        if CYC_DATA_IDX + size > N {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        let (command, words) = self.config_answer.take().unwrap_or((CFG_IDLE, [0u16; 4]));
+        self.frame.raw[HEADER_IDX] = self.frame.subnode as u16;
+        self.frame.raw[COMMAND_IDX] = command;
+        self.frame.raw[CFG_DATA_IDX..6].copy_from_slice(&words);
+        self.frame.raw[6] = self.interface.crc_checksum(&self.frame.raw[..6]);
+        self.frame.raw[CYC_DATA_IDX..CYC_DATA_IDX + size].copy_from_slice(data);
+
+        self.send(CYC_DATA_IDX + size)
     }
 
+    /// Checks if a frame is available. Waiting for a frame after the
+    /// watchdog expired fails with [`IntfError::LinkLoss`]
     pub fn listen(&mut self) -> Result<IntfResult, IntfError> {
-        self.interface.is_data2read()
+        match self.interface.is_data2read() {
+            Ok(IntfResult::Empty) if self.watchdog() == WatchdogState::Expired => {
+                Err(IntfError::LinkLoss)
+            }
+            result => result,
+        }
     }
 
-    pub fn into_cyclic(self) -> Node<Cyclic, INTF> {
-        Node {
-            frame: self.frame,
-            _state: Cyclic,
-            interface: self.interface,
-            ext_mode: self.ext_mode,
+    /// Checks the cycle timeout, calling the callback of
+    /// [`Node::on_cycle_timeout`] if it just expired
+    pub fn watchdog(&mut self) -> WatchdogState {
+        self.watchdog.check()
+    }
+
+    /// Moves back to config state without acknowledging, e.g. after the
+    /// watchdog expired. The Main finds the Node in config state once the
+    /// link is back
+    pub fn fall_back(self) -> Node<Config, INTF, N> {
+        self.transition(Config)
+    }
+
+    /// Acknowledges a state change request and moves back to config state. If
+    /// the request does not ask for config state or the acknowledge cannot be
+    /// sent, the Node is handed back in cyclic state
+    #[allow(clippy::result_large_err)]
+    pub fn into_config(
+        mut self,
+        request: &Request,
+    ) -> Result<Node<Config, INTF, N>, (Node<Cyclic, INTF, N>, IntfError)> {
+        if !matches!(
+            request.command,
+            CommandType::StateChange(TargetState::Config)
+        ) {
+            return Err((self, IntfError::WrongCommand));
+        }
+
+        match self.write_internal(0, CFG_STD_ACK) {
+            Ok(_) => Ok(self.transition(Config)),
+            Err(e) => Err((self, e)),
         }
     }
 }
 
-/// These functions may be used on any Mcb in config State
-impl<INTF> Node<Cyclic, INTF>
+/// Error code answered to requests of a served subnode without handler
+pub const ERR_NO_HANDLER: u32 = 0x0602_0000;
+
+type Handler<'a, INTF, const N: usize> =
+    Box<dyn FnMut(&mut Node<Config, INTF, N>, Request) -> Result<IntfResult, IntfError> + 'a>;
+
+/// Routes the requests of a Node to a handler per subnode, e.g. one for the
+/// communications subnode and one per axis, each with its own registers.
+/// State change requests are passed to the handlers too, which may only
+/// reject them. To accept one, take the Node back with
+/// [`Dispatcher::into_node`]
+pub struct Dispatcher<'a, INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    node: Node<Config, INTF, N>,
+    handlers: Vec<(u8, Handler<'a, INTF, N>)>,
+}
+
+impl<'a, INTF, const N: usize> Dispatcher<'a, INTF, N>
 where
     INTF: PhysicalInterface,
 {
-    pub fn into_config(self) -> Node<Config, INTF> {
-        Node {
-            frame: self.frame,
-            _state: Config,
-            interface: self.interface,
-            ext_mode: self.ext_mode,
+    pub fn new(node: Node<Config, INTF, N>) -> Dispatcher<'a, INTF, N> {
+        Dispatcher {
+            node,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Serves the subnode with the given handler, replacing any previous one
+    pub fn handle<F>(&mut self, subnode: u8, handler: F)
+    where
+        F: FnMut(&mut Node<Config, INTF, N>, Request) -> Result<IntfResult, IntfError> + 'a,
+    {
+        self.node.add_subnode(subnode);
+        self.handlers.retain(|(served, _)| *served != subnode);
+        self.handlers.push((subnode, Box::new(handler)));
+    }
+
+    /// Reads a request and passes it to the handler of its subnode
+    pub fn process(&mut self) -> Result<IntfResult, IntfError> {
+        let request = self.node.read()?;
+
+        match self
+            .handlers
+            .iter_mut()
+            .find(|(served, _)| *served == request.subnode)
+        {
+            Some((_, handler)) => handler(&mut self.node, request),
+            None => self.node.error(0, ERR_NO_HANDLER),
+        }
+    }
+
+    /// Serves requests until the interface fails. Frames that cannot be
+    /// decoded are dropped
+    pub fn run(&mut self) -> IntfError {
+        loop {
+            if let Err(IntfError::Interface) = self.process() {
+                return IntfError::Interface;
+            }
         }
     }
+
+    pub fn into_node(self) -> Node<Config, INTF, N> {
+        self.node
+    }
+}
+
+/// Starts the instrumentation of a received request
+fn begin_read(data: &[u16]) -> Transaction {
+    Transaction::begin(
+        "read",
+        data[HEADER_IDX] as u8 & 0xfu8,
+        data[COMMAND_IDX] >> 4,
+        data[COMMAND_IDX] & 0xfu16,
+    )
 }
 
 pub fn create_node_mcb<INTF: PhysicalInterface>(
@@ -369,15 +904,29 @@ pub fn create_node_mcb<INTF: PhysicalInterface>(
     mode: ExtMode,
     subnode: u8,
 ) -> Node<Init, INTF> {
+    create_sized_node_mcb(interface, mode, subnode)
+}
+
+/// Creates a Node using frames of `N` words. It must match the frame size of
+/// the Main. Requests not fitting in the frame are rejected with
+/// [`IntfError::InvalidParameter`]
+pub fn create_sized_node_mcb<INTF: PhysicalInterface, const N: usize>(
+    interface: Option<INTF>,
+    mode: ExtMode,
+    subnode: u8,
+) -> Node<Init, INTF, N> {
     let interface_in = interface.unwrap();
     Node {
-        frame: Frame {
-            _address: 0u16,
-            subnode,
-            raw: [0u16; MAX_FRAME_SIZE],
-        },
+        frame: Frame::new(subnode),
         _state: Init,
         interface: interface_in,
         ext_mode: mode,
+        subnodes: vec![subnode],
+        payload_crc: false,
+        stats: Statistics::default(),
+        watchdog: Watchdog::new(),
+        config_answer: None,
+        segments: Vec::new(),
+        outgoing: None,
     }
 }
@@ -0,0 +1,234 @@
+use crate::codec::{words_to_bytes, ByteOrder};
+use crate::mcb_main::Main;
+use crate::*;
+
+/// Subnode hosting the monitoring registers
+pub const MONITORING_SUBNODE: u8 = 0;
+/// Maximum number of registers that can be sampled at the same time
+pub const MAX_MON_CHANNELS: usize = 16;
+
+const MON_ENABLE: u16 = 0x0C0;
+const MON_STATUS: u16 = 0x0C1;
+const MON_TRIGGER_TYPE: u16 = 0x0C2;
+const MON_FREQ_DIV: u16 = 0x0C3;
+const MON_WINDOW_SAMP: u16 = 0x0C4;
+const MON_TRIGGER_CHANNEL: u16 = 0x0C5;
+const MON_TRIGGER_LEVEL: u16 = 0x0C6;
+const MON_FORCE_TRIGGER: u16 = 0x0C7;
+const MON_REMOVE_DATA: u16 = 0x0C8;
+const MON_TOTAL_MAP: u16 = 0x0D0;
+const MON_REG0_MAP: u16 = 0x0D1;
+const MON_DATA: u16 = 0x0B2;
+
+const MON_STATUS_ENABLED: u16 = 0x0001;
+const MON_STATUS_TRIGGERED: u16 = 0x0002;
+const MON_STATUS_READY: u16 = 0x0004;
+
+/// Register sampled by the monitoring
+#[derive(Debug, Clone, Copy)]
+pub struct Channel {
+    pub subnode: u8,
+    pub address: u16,
+    pub dtype: DataType,
+}
+
+impl Channel {
+    /// Value written to the mapping registers. It packs the address, the
+    /// subnode and the size in bytes of the register
+    pub(crate) fn mapping(&self) -> u32 {
+        (self.address as u32) | ((self.subnode as u32) << 12) | ((self.dtype.size() as u32) << 16)
+    }
+}
+
+/// Event starting the acquisition once the monitoring is enabled
+#[derive(Debug, Clone, Copy)]
+pub enum TriggerMode {
+    /// Samples are stored as soon as the monitoring is enabled
+    Auto,
+    /// Samples are stored after calling [`Monitoring::trigger`]
+    Forced,
+    /// Samples are stored when the given channel rises above the level
+    RisingEdge { channel: u16, level: i32 },
+    /// Samples are stored when the given channel falls below the level
+    FallingEdge { channel: u16, level: i32 },
+}
+
+/// Acquisition settings
+#[derive(Debug, Clone)]
+pub struct MonitoringConfig {
+    pub channels: Vec<Channel>,
+    pub trigger: TriggerMode,
+    /// Sample rate divisor applied to the drive monitoring frequency
+    pub divisor: u32,
+    /// Number of samples stored per channel
+    pub samples: u32,
+}
+
+impl MonitoringConfig {
+    /// Number of bytes used by a single sample of all the channels
+    fn stride(&self) -> usize {
+        self.channels
+            .iter()
+            .map(|channel| channel.dtype.size())
+            .sum()
+    }
+}
+
+/// Handle to the monitoring of a drive. See [`Main::monitoring`]
+pub struct Monitoring<'a, INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    main: &'a mut Main<Config, INTF, N>,
+    config: &'a MonitoringConfig,
+}
+
+impl<INTF, const N: usize> Main<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Gives access to the monitoring of the drive using the given settings
+    pub fn monitoring<'a>(&'a mut self, config: &'a MonitoringConfig) -> Monitoring<'a, INTF, N> {
+        Monitoring { main: self, config }
+    }
+}
+
+impl<INTF, const N: usize> Monitoring<'_, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Disables the monitoring, removes any previous data and writes the
+    /// channel mapping, trigger, divisor and window settings
+    pub fn configure(&mut self) -> Result<IntfResult, IntfError> {
+        let config = self.config;
+        if config.channels.is_empty() || config.channels.len() > MAX_MON_CHANNELS {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        if config
+            .channels
+            .iter()
+            .any(|channel| channel.dtype == DataType::Str)
+        {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        self.disable()?;
+        self.main
+            .write_u16(MONITORING_SUBNODE, MON_REMOVE_DATA, 1u16)?;
+        self.main
+            .write_u16(MONITORING_SUBNODE, MON_TOTAL_MAP, 0u16)?;
+
+        for (index, channel) in config.channels.iter().enumerate() {
+            self.main.write_u32(
+                MONITORING_SUBNODE,
+                MON_REG0_MAP + index as u16,
+                channel.mapping(),
+            )?;
+        }
+
+        self.main.write_u16(
+            MONITORING_SUBNODE,
+            MON_TOTAL_MAP,
+            config.channels.len() as u16,
+        )?;
+        self.main
+            .write_u32(MONITORING_SUBNODE, MON_FREQ_DIV, config.divisor)?;
+        self.main
+            .write_u32(MONITORING_SUBNODE, MON_WINDOW_SAMP, config.samples)?;
+
+        let (trigger_type, edge) = match config.trigger {
+            TriggerMode::Auto => (0u16, None),
+            TriggerMode::Forced => (1u16, None),
+            TriggerMode::RisingEdge { channel, level } => (2u16, Some((channel, level))),
+            TriggerMode::FallingEdge { channel, level } => (3u16, Some((channel, level))),
+        };
+
+        if let Some((channel, level)) = edge {
+            if channel as usize >= config.channels.len() {
+                return Err(IntfError::InvalidParameter);
+            }
+            self.main
+                .write_u16(MONITORING_SUBNODE, MON_TRIGGER_CHANNEL, channel)?;
+            self.main
+                .write_i32(MONITORING_SUBNODE, MON_TRIGGER_LEVEL, level)?;
+        }
+
+        self.main
+            .write_u16(MONITORING_SUBNODE, MON_TRIGGER_TYPE, trigger_type)
+    }
+
+    /// Arms the monitoring. Samples are stored once the trigger condition is met
+    pub fn enable(&mut self) -> Result<IntfResult, IntfError> {
+        self.main.write_u16(MONITORING_SUBNODE, MON_ENABLE, 1u16)
+    }
+
+    pub fn disable(&mut self) -> Result<IntfResult, IntfError> {
+        self.main.write_u16(MONITORING_SUBNODE, MON_ENABLE, 0u16)
+    }
+
+    /// Forces the trigger event
+    pub fn trigger(&mut self) -> Result<IntfResult, IntfError> {
+        self.main
+            .write_u16(MONITORING_SUBNODE, MON_FORCE_TRIGGER, 1u16)
+    }
+
+    pub fn is_enabled(&mut self) -> Result<bool, IntfError> {
+        self.status()
+            .map(|status| (status & MON_STATUS_ENABLED) != 0)
+    }
+
+    pub fn is_triggered(&mut self) -> Result<bool, IntfError> {
+        self.status()
+            .map(|status| (status & MON_STATUS_TRIGGERED) != 0)
+    }
+
+    /// Checks if the acquisition finished and the buffer can be fetched
+    pub fn is_ready(&mut self) -> Result<bool, IntfError> {
+        self.status().map(|status| (status & MON_STATUS_READY) != 0)
+    }
+
+    fn status(&mut self) -> Result<u16, IntfError> {
+        self.main.read_u16(MONITORING_SUBNODE, MON_STATUS)
+    }
+
+    /// Reads the acquired buffer and splits it into one list of samples per
+    /// channel, in the same order as the configured channels
+    pub fn fetch(&mut self) -> Result<Vec<Samples>, IntfError> {
+        let config = self.config;
+        let stride = config.stride();
+        let expected = stride * config.samples as usize;
+        let mut buffer = Vec::with_capacity(expected);
+
+        while buffer.len() < expected {
+            let words = self.main.read_words(MONITORING_SUBNODE, MON_DATA)?;
+            if words.is_empty() {
+                return Err(IntfError::Interface);
+            }
+            buffer.extend(words_to_bytes(&words, ByteOrder::Little));
+        }
+        buffer.truncate(expected);
+
+        let mut result: Vec<Samples> = match config
+            .channels
+            .iter()
+            .map(|channel| Samples::new(channel.dtype))
+            .collect()
+        {
+            Some(result) => result,
+            None => return Err(IntfError::InvalidParameter),
+        };
+
+        if stride == 0 {
+            return Ok(result);
+        }
+
+        for sample in buffer.chunks_exact(stride) {
+            let mut offset = 0;
+            for (channel, samples) in config.channels.iter().zip(result.iter_mut()) {
+                let size = channel.dtype.size();
+                samples.push_le(&sample[offset..offset + size]);
+                offset += size;
+            }
+        }
+
+        Ok(result)
+    }
+}
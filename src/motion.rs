@@ -0,0 +1,164 @@
+use crate::cia402::{Cia402, OperationMode, CW_ENABLE_OPERATION};
+use crate::*;
+
+use std::time::Instant;
+
+const TARGET_POSITION: u16 = 0x020;
+const TARGET_VELOCITY: u16 = 0x021;
+const PROFILE_VELOCITY: u16 = 0x022;
+const PROFILE_ACCELERATION: u16 = 0x023;
+const PROFILE_DECELERATION: u16 = 0x024;
+const HOMING_METHOD: u16 = 0x028;
+const HOMING_SPEED: u16 = 0x029;
+const HOMING_ACCELERATION: u16 = 0x02A;
+const HOMING_OFFSET: u16 = 0x02B;
+const ACTUAL_POSITION: u16 = 0x030;
+const ACTUAL_VELOCITY: u16 = 0x031;
+
+const CW_NEW_SET_POINT: u16 = 0x0010;
+const CW_CHANGE_IMMEDIATELY: u16 = 0x0020;
+const CW_RELATIVE: u16 = 0x0040;
+const CW_HALT: u16 = 0x0100;
+const CW_HOMING_START: u16 = 0x0010;
+
+const SW_TARGET_REACHED: u16 = 0x0400;
+const SW_SET_POINT_ACK: u16 = 0x1000;
+const SW_HOMING_ATTAINED: u16 = 0x1000;
+const SW_FOLLOWING_ERROR: u16 = 0x2000;
+const SW_HOMING_ERROR: u16 = 0x2000;
+
+/// Settings of a homing procedure
+#[derive(Debug, Clone, Copy)]
+pub struct Homing {
+    /// Homing method as defined by CiA-402
+    pub method: i8,
+    pub speed: u32,
+    pub acceleration: u32,
+    /// Position assigned to the home position
+    pub offset: i32,
+}
+
+/// Settings of a profile position move
+#[derive(Debug, Clone, Copy)]
+pub struct ProfileMove {
+    pub target: i32,
+    pub velocity: u32,
+    pub acceleration: u32,
+    pub deceleration: u32,
+    /// The target is relative to the current target position
+    pub relative: bool,
+}
+
+/// Motion commands. The axis must be in operation enabled, see [`Cia402::enable`].
+/// All waits use the timeout of the state machine handle
+impl<INTF, const N: usize> Cia402<'_, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Runs a homing procedure and waits until it finishes
+    pub fn home(&mut self, homing: &Homing) -> Result<IntfResult, IntfError> {
+        self.set_operation_mode(OperationMode::Homing)?;
+        self.main
+            .write_i8(self.subnode, HOMING_METHOD, homing.method)?;
+        self.main
+            .write_u32(self.subnode, HOMING_SPEED, homing.speed)?;
+        self.main
+            .write_u32(self.subnode, HOMING_ACCELERATION, homing.acceleration)?;
+        self.main
+            .write_i32(self.subnode, HOMING_OFFSET, homing.offset)?;
+
+        self.control_word(CW_ENABLE_OPERATION)?;
+        self.control_word(CW_ENABLE_OPERATION | CW_HOMING_START)?;
+
+        let result = self.wait_status(SW_HOMING_ATTAINED | SW_TARGET_REACHED, SW_HOMING_ERROR);
+        self.control_word(CW_ENABLE_OPERATION)?;
+        result
+    }
+
+    /// Sends a profile position move without waiting for the target to be
+    /// reached. Returns once the drive acknowledges the new set-point
+    pub fn start_move(&mut self, profile: &ProfileMove) -> Result<IntfResult, IntfError> {
+        self.set_operation_mode(OperationMode::ProfilePosition)?;
+        self.main
+            .write_u32(self.subnode, PROFILE_VELOCITY, profile.velocity)?;
+        self.main
+            .write_u32(self.subnode, PROFILE_ACCELERATION, profile.acceleration)?;
+        self.main
+            .write_u32(self.subnode, PROFILE_DECELERATION, profile.deceleration)?;
+        self.main
+            .write_i32(self.subnode, TARGET_POSITION, profile.target)?;
+
+        let mut control = CW_ENABLE_OPERATION | CW_CHANGE_IMMEDIATELY;
+        if profile.relative {
+            control |= CW_RELATIVE;
+        }
+
+        self.control_word(control)?;
+        self.control_word(control | CW_NEW_SET_POINT)?;
+
+        let result = self.wait_status(SW_SET_POINT_ACK, SW_FOLLOWING_ERROR);
+        self.control_word(control)?;
+        result
+    }
+
+    /// Waits until the drive reports the target is reached
+    pub fn wait_target_reached(&mut self) -> Result<IntfResult, IntfError> {
+        self.wait_status(SW_TARGET_REACHED, SW_FOLLOWING_ERROR)
+    }
+
+    /// Moves the axis to the target position and waits until it is reached
+    pub fn move_to(&mut self, profile: &ProfileMove) -> Result<IntfResult, IntfError> {
+        self.start_move(profile)?;
+        self.wait_target_reached()
+    }
+
+    /// Sets the target of the profile velocity mode. The axis keeps moving
+    /// until a new target is set or it is halted
+    pub fn set_velocity(
+        &mut self,
+        velocity: i32,
+        acceleration: u32,
+    ) -> Result<IntfResult, IntfError> {
+        self.set_operation_mode(OperationMode::ProfileVelocity)?;
+        self.main
+            .write_u32(self.subnode, PROFILE_ACCELERATION, acceleration)?;
+        self.main
+            .write_u32(self.subnode, PROFILE_DECELERATION, acceleration)?;
+        self.main
+            .write_i32(self.subnode, TARGET_VELOCITY, velocity)?;
+        self.control_word(CW_ENABLE_OPERATION)
+    }
+
+    /// Decelerates the axis to stand still keeping the power stage enabled
+    pub fn halt(&mut self) -> Result<IntfResult, IntfError> {
+        self.control_word(CW_ENABLE_OPERATION | CW_HALT)
+    }
+
+    pub fn position(&mut self) -> Result<i32, IntfError> {
+        self.main.read_i32(self.subnode, ACTUAL_POSITION)
+    }
+
+    pub fn velocity(&mut self) -> Result<i32, IntfError> {
+        self.main.read_i32(self.subnode, ACTUAL_VELOCITY)
+    }
+
+    /// Polls the status word until all the `done` bits are set. Any of the
+    /// `error` bits aborts the wait with [`IntfError::Motion`]
+    fn wait_status(&mut self, done: u16, error: u16) -> Result<IntfResult, IntfError> {
+        let start = Instant::now();
+        loop {
+            let status = self.status_word()?;
+            if (status & error) != 0 {
+                return Err(IntfError::Motion(status));
+            }
+
+            if (status & done) == done {
+                return Ok(IntfResult::Success);
+            }
+
+            if start.elapsed() > self.timeout {
+                return Err(IntfError::Timeout);
+            }
+        }
+    }
+}
@@ -0,0 +1,579 @@
+use crate::mcb_main::Main;
+use crate::*;
+
+/// Access rights of a register
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+/// Register that may take part in a parameter set. Only `ReadWrite`
+/// registers are saved and restored
+#[derive(Debug, Clone)]
+pub struct RegisterDescription {
+    pub name: String,
+    pub subnode: u8,
+    pub address: u16,
+    pub dtype: DataType,
+    pub access: Access,
+    /// Registers that must be written before this one
+    pub depends_on: Vec<String>,
+}
+
+impl RegisterDescription {
+    pub fn new(
+        name: &str,
+        subnode: u8,
+        address: u16,
+        dtype: DataType,
+        access: Access,
+    ) -> RegisterDescription {
+        RegisterDescription {
+            name: name.to_string(),
+            subnode,
+            address,
+            dtype,
+            access,
+            depends_on: Vec::new(),
+        }
+    }
+
+    /// Requires the register `name` to be written before this one
+    pub fn after(mut self, name: &str) -> RegisterDescription {
+        self.depends_on.push(name.to_string());
+        self
+    }
+
+    fn is_parameter(&self) -> bool {
+        self.access == Access::ReadWrite
+    }
+}
+
+/// Typed value of a single register
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Str(String),
+}
+
+impl Value {
+    pub fn dtype(&self) -> DataType {
+        match self {
+            Value::U8(_) => DataType::U8,
+            Value::I8(_) => DataType::I8,
+            Value::U16(_) => DataType::U16,
+            Value::I16(_) => DataType::I16,
+            Value::U32(_) => DataType::U32,
+            Value::I32(_) => DataType::I32,
+            Value::U64(_) => DataType::U64,
+            Value::I64(_) => DataType::I64,
+            Value::F32(_) => DataType::F32,
+            Value::F64(_) => DataType::F64,
+            Value::Str(_) => DataType::Str,
+        }
+    }
+
+    /// Little endian config words of the value. Floats are sent as their
+    /// IEEE-754 bits and strings are terminated with a zero
+    pub(crate) fn to_words(&self) -> Vec<u16> {
+        match self {
+            Value::U8(data) => vec![*data as u16],
+            Value::I8(data) => vec![*data as u8 as u16],
+            Value::U16(data) => vec![*data],
+            Value::I16(data) => vec![*data as u16],
+            Value::U32(data) => u64_words(*data as u64, 2),
+            Value::I32(data) => u64_words(*data as u32 as u64, 2),
+            Value::U64(data) => u64_words(*data, 4),
+            Value::I64(data) => u64_words(*data as u64, 4),
+            Value::F32(data) => u64_words(data.to_bits() as u64, 2),
+            Value::F64(data) => u64_words(data.to_bits(), 4),
+            Value::Str(data) => {
+                let mut bytes = data.as_bytes().to_vec();
+                bytes.push(0);
+                codec::bytes_to_words(&bytes, codec::ByteOrder::Little)
+            }
+        }
+    }
+
+    /// Value of the given type held by config words. Missing words are read
+    /// as zero
+    pub(crate) fn from_words(dtype: DataType, words: &[u16]) -> Value {
+        let word = |index: usize| words.get(index).copied().unwrap_or(0) as u64;
+        let long = word(0) | (word(1) << 16) | (word(2) << 32) | (word(3) << 48);
+        match dtype {
+            DataType::U8 => Value::U8(long as u8),
+            DataType::I8 => Value::I8(long as i8),
+            DataType::U16 => Value::U16(long as u16),
+            DataType::I16 => Value::I16(long as i16),
+            DataType::U32 => Value::U32(long as u32),
+            DataType::I32 => Value::I32(long as i32),
+            DataType::U64 => Value::U64(long),
+            DataType::I64 => Value::I64(long as i64),
+            DataType::F32 => Value::F32(f32::from_bits(long as u32)),
+            DataType::F64 => Value::F64(f64::from_bits(long)),
+            DataType::Str => Value::Str(codec::decode_str(words)),
+        }
+    }
+}
+
+fn u64_words(data: u64, count: usize) -> Vec<u16> {
+    (0..count)
+        .map(|index| (data >> (16 * index)) as u16)
+        .collect()
+}
+
+/// Saved value of a register
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub subnode: u8,
+    pub address: u16,
+    pub value: Value,
+}
+
+/// Values of the configuration registers of a drive
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParameterSet {
+    pub parameters: Vec<Parameter>,
+}
+
+impl ParameterSet {
+    pub fn get(&self, name: &str) -> Option<&Parameter> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.name == name)
+    }
+}
+
+/// Failure related to a single register
+#[derive(Debug)]
+pub struct ParameterError {
+    pub name: String,
+    pub error: IntfError,
+}
+
+/// Register whose read back value differs from the restored one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub name: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+/// Outcome of a restore. Failing registers do not stop the restore
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// Registers written, in write order
+    pub written: Vec<String>,
+    pub differences: Vec<Difference>,
+    pub failures: Vec<ParameterError>,
+    /// Parameters of the set without a writable register description
+    pub skipped: Vec<String>,
+}
+
+impl RestoreReport {
+    /// Whether every parameter was written and read back unchanged
+    pub fn is_clean(&self) -> bool {
+        self.differences.is_empty() && self.failures.is_empty() && self.skipped.is_empty()
+    }
+}
+
+impl<INTF, const N: usize> Main<Config, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Reads a register as a value of the given type
+    pub fn read_value(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        dtype: DataType,
+    ) -> Result<Value, IntfError> {
+        Ok(match dtype {
+            DataType::U8 => Value::U8(self.read_u8(subnode, add)?),
+            DataType::I8 => Value::I8(self.read_i8(subnode, add)?),
+            DataType::U16 => Value::U16(self.read_u16(subnode, add)?),
+            DataType::I16 => Value::I16(self.read_i16(subnode, add)?),
+            DataType::U32 => Value::U32(self.read_u32(subnode, add)?),
+            DataType::I32 => Value::I32(self.read_i32(subnode, add)?),
+            DataType::U64 => Value::U64(self.read_u64(subnode, add)?),
+            DataType::I64 => Value::I64(self.read_i64(subnode, add)?),
+            DataType::F32 => Value::F32(self.read_f32(subnode, add)?),
+            DataType::F64 => Value::F64(self.read_f64(subnode, add)?),
+            DataType::Str => Value::Str(self.read_str(subnode, add)?),
+        })
+    }
+
+    pub fn write_value(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        value: &Value,
+    ) -> Result<IntfResult, IntfError> {
+        match value {
+            Value::U8(data) => self.write_u8(subnode, add, *data),
+            Value::I8(data) => self.write_i8(subnode, add, *data),
+            Value::U16(data) => self.write_u16(subnode, add, *data),
+            Value::I16(data) => self.write_i16(subnode, add, *data),
+            Value::U32(data) => self.write_u32(subnode, add, *data),
+            Value::I32(data) => self.write_i32(subnode, add, *data),
+            Value::U64(data) => self.write_u64(subnode, add, *data),
+            Value::I64(data) => self.write_i64(subnode, add, *data),
+            Value::F32(data) => self.write_f32(subnode, add, *data),
+            Value::F64(data) => self.write_f64(subnode, add, *data),
+            Value::Str(data) => self.write_str(subnode, add, data),
+        }
+    }
+
+    /// Reads every readable and writable register of the list. Stops at the
+    /// first register that cannot be read
+    pub fn save_parameters(
+        &mut self,
+        registers: &[RegisterDescription],
+    ) -> Result<ParameterSet, ParameterError> {
+        let mut parameters = Vec::new();
+
+        for register in registers.iter().filter(|register| register.is_parameter()) {
+            let value = self
+                .read_value(register.subnode, register.address, register.dtype)
+                .map_err(|error| ParameterError {
+                    name: register.name.clone(),
+                    error,
+                })?;
+            parameters.push(Parameter {
+                name: register.name.clone(),
+                subnode: register.subnode,
+                address: register.address,
+                value,
+            });
+        }
+
+        Ok(ParameterSet { parameters })
+    }
+
+    /// Writes the parameters of the set to the registers with the same name,
+    /// dependencies first, and reads every written register back. The
+    /// addresses of the descriptions are used, so a set can be restored on a
+    /// drive with a different register map. Fails without writing anything
+    /// if the dependencies are unknown or circular
+    pub fn restore_parameters(
+        &mut self,
+        registers: &[RegisterDescription],
+        set: &ParameterSet,
+    ) -> Result<RestoreReport, ParameterError> {
+        let order = write_order(registers)?;
+        let mut report = RestoreReport::default();
+
+        for parameter in &set.parameters {
+            if !order.iter().any(|register| register.name == parameter.name) {
+                report.skipped.push(parameter.name.clone());
+            }
+        }
+
+        for register in order {
+            let Some(parameter) = set.get(&register.name) else {
+                continue;
+            };
+
+            match self.restore_parameter(register, &parameter.value) {
+                Ok(actual) => {
+                    report.written.push(register.name.clone());
+                    if actual != parameter.value {
+                        report.differences.push(Difference {
+                            name: register.name.clone(),
+                            expected: parameter.value.clone(),
+                            actual,
+                        });
+                    }
+                }
+                Err(error) => report.failures.push(ParameterError {
+                    name: register.name.clone(),
+                    error,
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn restore_parameter(
+        &mut self,
+        register: &RegisterDescription,
+        value: &Value,
+    ) -> Result<Value, IntfError> {
+        if value.dtype() != register.dtype {
+            return Err(IntfError::InvalidParameter);
+        }
+
+        self.write_value(register.subnode, register.address, value)?;
+        self.read_value(register.subnode, register.address, register.dtype)
+    }
+}
+
+impl<INTF, const N: usize> Main<Cyclic, INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    /// Reads a register as a value of the given type, over the config words
+    /// of the cyclic frames
+    pub fn read_value(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        dtype: DataType,
+    ) -> Result<Value, IntfError> {
+        let words = self.read_words(subnode, add)?;
+        Ok(Value::from_words(dtype, &words))
+    }
+
+    pub fn write_value(
+        &mut self,
+        subnode: u8,
+        add: u16,
+        value: &Value,
+    ) -> Result<IntfResult, IntfError> {
+        self.write_words(subnode, add, &value.to_words())
+    }
+}
+
+/// Writable registers sorted so that every register comes after the ones it
+/// depends on. Independent registers keep the order of the list
+fn write_order(
+    registers: &[RegisterDescription],
+) -> Result<Vec<&RegisterDescription>, ParameterError> {
+    let invalid = |name: &str| ParameterError {
+        name: name.to_string(),
+        error: IntfError::InvalidParameter,
+    };
+
+    for register in registers {
+        for dependency in &register.depends_on {
+            if !registers.iter().any(|other| other.name == *dependency) {
+                return Err(invalid(dependency));
+            }
+        }
+    }
+
+    let mut pending: Vec<&RegisterDescription> = registers.iter().collect();
+    let mut order: Vec<&RegisterDescription> = Vec::new();
+
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|register| {
+                register
+                    .depends_on
+                    .iter()
+                    .all(|dependency| order.iter().any(|done| done.name == *dependency))
+            })
+            .ok_or_else(|| invalid(&pending[0].name))?;
+        order.push(pending.remove(ready));
+    }
+
+    order.retain(|register| register.is_parameter());
+    Ok(order)
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+pub use file::FormatError;
+
+/// Parameter files. Both formats hold a list of `parameter` entries with the
+/// `name`, `subnode`, `address`, `dtype` and `value` of each register.
+/// Integers that do not fit an i64 and non finite floats are stored as text
+#[cfg(any(feature = "toml", feature = "json"))]
+mod file {
+    use super::{Parameter, ParameterSet, Value};
+    use crate::DataType;
+
+    use serde::{Deserialize, Serialize};
+    use std::fmt;
+
+    /// Failure to encode or decode a parameter file
+    #[derive(Debug)]
+    pub struct FormatError(pub String);
+
+    impl fmt::Display for FormatError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct File {
+        #[serde(default)]
+        parameter: Vec<Entry>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Entry {
+        name: String,
+        subnode: u8,
+        address: u16,
+        dtype: String,
+        value: EntryValue,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum EntryValue {
+        Integer(i64),
+        Float(f64),
+        Text(String),
+    }
+
+    fn integer<T: Into<i64>>(value: T) -> EntryValue {
+        EntryValue::Integer(value.into())
+    }
+
+    /// Stores the shortest text that reads back to the same value, so f32
+    /// values do not show their f64 widening in the file
+    fn float<T: ToString>(value: T, finite: bool) -> EntryValue {
+        let text = value.to_string();
+        match text.parse() {
+            Ok(value) if finite => EntryValue::Float(value),
+            _ => EntryValue::Text(text),
+        }
+    }
+
+    impl From<&Value> for EntryValue {
+        fn from(value: &Value) -> EntryValue {
+            match value {
+                Value::U8(v) => integer(*v),
+                Value::I8(v) => integer(*v),
+                Value::U16(v) => integer(*v),
+                Value::I16(v) => integer(*v),
+                Value::U32(v) => integer(*v),
+                Value::I32(v) => integer(*v),
+                Value::U64(v) => match i64::try_from(*v) {
+                    Ok(v) => EntryValue::Integer(v),
+                    Err(_) => EntryValue::Text(v.to_string()),
+                },
+                Value::I64(v) => integer(*v),
+                Value::F32(v) => float(*v, v.is_finite()),
+                Value::F64(v) => float(*v, v.is_finite()),
+                Value::Str(v) => EntryValue::Text(v.clone()),
+            }
+        }
+    }
+
+    impl EntryValue {
+        fn to_value(&self, dtype: DataType) -> Option<Value> {
+            fn int<T: TryFrom<i64> + std::str::FromStr>(value: &EntryValue) -> Option<T> {
+                match value {
+                    EntryValue::Integer(v) => T::try_from(*v).ok(),
+                    EntryValue::Text(v) => v.parse().ok(),
+                    EntryValue::Float(_) => None,
+                }
+            }
+
+            fn real(value: &EntryValue) -> Option<f64> {
+                match value {
+                    EntryValue::Integer(v) => Some(*v as f64),
+                    EntryValue::Float(v) => Some(*v),
+                    EntryValue::Text(v) => v.parse().ok(),
+                }
+            }
+
+            Some(match dtype {
+                DataType::U8 => Value::U8(int(self)?),
+                DataType::I8 => Value::I8(int(self)?),
+                DataType::U16 => Value::U16(int(self)?),
+                DataType::I16 => Value::I16(int(self)?),
+                DataType::U32 => Value::U32(int(self)?),
+                DataType::I32 => Value::I32(int(self)?),
+                DataType::U64 => Value::U64(int(self)?),
+                DataType::I64 => Value::I64(int(self)?),
+                DataType::F32 => match self {
+                    EntryValue::Text(v) => Value::F32(v.parse().ok()?),
+                    _ => Value::F32(real(self)? as f32),
+                },
+                DataType::F64 => Value::F64(real(self)?),
+                DataType::Str => match self {
+                    EntryValue::Text(v) => Value::Str(v.clone()),
+                    _ => return None,
+                },
+            })
+        }
+    }
+
+    impl From<&ParameterSet> for File {
+        fn from(set: &ParameterSet) -> File {
+            File {
+                parameter: set
+                    .parameters
+                    .iter()
+                    .map(|parameter| Entry {
+                        name: parameter.name.clone(),
+                        subnode: parameter.subnode,
+                        address: parameter.address,
+                        dtype: parameter.value.dtype().name().to_string(),
+                        value: EntryValue::from(&parameter.value),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl TryFrom<File> for ParameterSet {
+        type Error = FormatError;
+
+        fn try_from(file: File) -> Result<ParameterSet, FormatError> {
+            let parameters = file
+                .parameter
+                .into_iter()
+                .map(|entry| {
+                    let dtype = DataType::from_name(&entry.dtype).ok_or_else(|| {
+                        FormatError(format!("{}: unknown type `{}`", entry.name, entry.dtype))
+                    })?;
+                    let value = entry.value.to_value(dtype).ok_or_else(|| {
+                        FormatError(format!("{}: invalid {} value", entry.name, entry.dtype))
+                    })?;
+                    Ok(Parameter {
+                        name: entry.name,
+                        subnode: entry.subnode,
+                        address: entry.address,
+                        value,
+                    })
+                })
+                .collect::<Result<Vec<Parameter>, FormatError>>()?;
+
+            Ok(ParameterSet { parameters })
+        }
+    }
+
+    impl ParameterSet {
+        #[cfg(feature = "toml")]
+        pub fn to_toml(&self) -> Result<String, FormatError> {
+            toml::to_string(&File::from(self)).map_err(|e| FormatError(e.to_string()))
+        }
+
+        #[cfg(feature = "toml")]
+        pub fn from_toml(content: &str) -> Result<ParameterSet, FormatError> {
+            let file: File = toml::from_str(content).map_err(|e| FormatError(e.to_string()))?;
+            ParameterSet::try_from(file)
+        }
+
+        #[cfg(feature = "json")]
+        pub fn to_json(&self) -> Result<String, FormatError> {
+            serde_json::to_string_pretty(&File::from(self)).map_err(|e| FormatError(e.to_string()))
+        }
+
+        #[cfg(feature = "json")]
+        pub fn from_json(content: &str) -> Result<ParameterSet, FormatError> {
+            let file: File =
+                serde_json::from_str(content).map_err(|e| FormatError(e.to_string()))?;
+            ParameterSet::try_from(file)
+        }
+    }
+}
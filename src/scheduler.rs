@@ -0,0 +1,206 @@
+use crate::mcb_main::Main;
+use crate::stats::LatencyHistogram;
+use crate::*;
+
+use std::time::{Duration, Instant};
+
+/// Monotonic clock the scheduler waits on. Implement it over a hardware
+/// timer on embedded targets
+pub trait Timer {
+    /// Time elapsed since an arbitrary, fixed origin
+    fn now(&mut self) -> Duration;
+
+    /// Sleeps until [`Timer::now`] reaches the given absolute deadline.
+    /// Returns at once if it already passed
+    fn sleep_until(&mut self, deadline: Duration);
+}
+
+/// Timer built on [`Instant`] and [`std::thread::sleep`]
+pub struct StdTimer {
+    origin: Instant,
+}
+
+impl StdTimer {
+    pub fn new() -> StdTimer {
+        StdTimer {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for StdTimer {
+    fn default() -> StdTimer {
+        StdTimer::new()
+    }
+}
+
+impl Timer for StdTimer {
+    fn now(&mut self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn sleep_until(&mut self, deadline: Duration) {
+        let now = self.now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+    }
+}
+
+/// Timer sleeping with `clock_nanosleep` on absolute deadlines of
+/// `CLOCK_MONOTONIC`, so that preemption between reading the clock and
+/// sleeping does not delay the wake up. Needs the `rt` feature
+#[cfg(all(feature = "rt", target_os = "linux"))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MonotonicTimer;
+
+#[cfg(all(feature = "rt", target_os = "linux"))]
+impl Timer for MonotonicTimer {
+    fn now(&mut self) -> Duration {
+        use nix::time::{clock_gettime, ClockId};
+        clock_gettime(ClockId::CLOCK_MONOTONIC)
+            .map(Duration::from)
+            .unwrap_or_default()
+    }
+
+    fn sleep_until(&mut self, deadline: Duration) {
+        use nix::errno::Errno;
+        use nix::time::{clock_nanosleep, ClockId, ClockNanosleepFlags};
+        let request = deadline.into();
+        while let Err(Errno::EINTR) = clock_nanosleep(
+            ClockId::CLOCK_MONOTONIC,
+            ClockNanosleepFlags::TIMER_ABSTIME,
+            &request,
+        ) {}
+    }
+}
+
+/// Timing of the cycles run by a [`Scheduler`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CycleStatistics {
+    pub cycles: u64,
+    /// Cycles that failed, see [`Main::cycle`]
+    pub errors: u64,
+    /// Cycles that ended after the deadline of the next one
+    pub overruns: u64,
+    /// Deadlines skipped to recover from the overruns
+    pub missed_deadlines: u64,
+    /// Delay between each deadline and the actual start of its cycle
+    pub jitter: LatencyHistogram,
+}
+
+/// Runs the cyclic exchange at a fixed period. Deadlines are absolute, a
+/// whole number of periods after the first one, so the period does not
+/// drift with the time taken by each cycle
+pub struct Scheduler<T: Timer> {
+    timer: T,
+    period: Duration,
+    deadline: Option<Duration>,
+    stats: CycleStatistics,
+}
+
+impl<T> Scheduler<T>
+where
+    T: Timer,
+{
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Waits for the next deadline and exchanges `output` with the subnode.
+    /// The callback receives the cyclic data of the answer and updates
+    /// `output` for the next cycle. The first cycle starts at once
+    pub fn cycle<INTF, F, const N: usize>(
+        &mut self,
+        main: &mut Main<Cyclic, INTF, N>,
+        subnode: u8,
+        output: &mut [u16],
+        callback: F,
+    ) -> Result<IntfResult, IntfError>
+    where
+        INTF: PhysicalInterface,
+        F: FnOnce(&[u16], &mut [u16]),
+    {
+        let deadline = match self.deadline {
+            Some(deadline) => deadline,
+            None => self.timer.now(),
+        };
+        self.timer.sleep_until(deadline);
+        let start = self.timer.now();
+        self.stats.jitter.record(start.saturating_sub(deadline));
+        self.stats.cycles += 1;
+
+        let result = main.cycle(subnode, output);
+        if let Ok(input) = &result {
+            callback(input, output);
+        }
+
+        let mut next = deadline + self.period;
+        let end = self.timer.now();
+        if end > next {
+            self.stats.overruns += 1;
+            let missed = ((end - next).as_nanos() / self.period.as_nanos().max(1)) as u32 + 1;
+            self.stats.missed_deadlines += missed as u64;
+            next += self.period * missed;
+        }
+        self.deadline = Some(next);
+
+        match result {
+            Ok(_) => Ok(IntfResult::Success),
+            Err(e) => {
+                self.stats.errors += 1;
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs cycles until the callback returns false or a cycle fails. With a
+    /// link loss threshold, [`Scheduler::cycle`] may be used instead to ride
+    /// through single failures
+    pub fn run<INTF, F, const N: usize>(
+        &mut self,
+        main: &mut Main<Cyclic, INTF, N>,
+        subnode: u8,
+        output: &mut [u16],
+        mut callback: F,
+    ) -> Result<IntfResult, IntfError>
+    where
+        INTF: PhysicalInterface,
+        F: FnMut(&[u16], &mut [u16]) -> bool,
+    {
+        let mut running = true;
+        while running {
+            self.cycle(main, subnode, output, |input, output| {
+                running = callback(input, output);
+            })?;
+        }
+        Ok(IntfResult::Success)
+    }
+
+    /// Starts again from the next cycle, e.g. after a pause
+    pub fn restart(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Snapshot of the timing of the cycles
+    pub fn statistics(&self) -> CycleStatistics {
+        self.stats.clone()
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.stats = CycleStatistics::default();
+    }
+
+    pub fn into_timer(self) -> T {
+        self.timer
+    }
+}
+
+pub fn create_scheduler<T: Timer>(period: Duration, timer: T) -> Scheduler<T> {
+    Scheduler {
+        timer,
+        period,
+        deadline: None,
+        stats: CycleStatistics::default(),
+    }
+}
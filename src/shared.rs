@@ -0,0 +1,235 @@
+use crate::mcb_main::Main;
+use crate::parameters::Value;
+use crate::*;
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Answer to a read submitted to a [`SharedMain`]
+pub type ReadReply = Receiver<Result<Value, IntfError>>;
+/// Answer to a write submitted to a [`SharedMain`]
+pub type WriteReply = Receiver<Result<IntfResult, IntfError>>;
+
+enum Access {
+    Read(DataType, Sender<Result<Value, IntfError>>),
+    Write(Value, Sender<Result<IntfResult, IntfError>>),
+}
+
+struct Request {
+    subnode: u8,
+    add: u16,
+    access: Access,
+}
+
+impl Request {
+    fn execute<INTF: PhysicalInterface, const N: usize>(self, main: &mut Main<Config, INTF, N>) {
+        match self.access {
+            Access::Read(dtype, reply) => {
+                let _ = reply.send(main.read_value(self.subnode, self.add, dtype));
+            }
+            Access::Write(value, reply) => {
+                let _ = reply.send(main.write_value(self.subnode, self.add, &value));
+            }
+        }
+    }
+
+    fn start<INTF: PhysicalInterface, const N: usize>(
+        &self,
+        main: &mut Main<Cyclic, INTF, N>,
+    ) -> Result<IntfResult, IntfError> {
+        match &self.access {
+            Access::Read(_, _) => main.start_read(self.subnode, self.add),
+            Access::Write(value, _) => main.start_write(self.subnode, self.add, &value.to_words()),
+        }
+    }
+
+    fn complete(self, answer: Result<Vec<u16>, IntfError>) {
+        match self.access {
+            Access::Read(dtype, reply) => {
+                let _ = reply.send(answer.map(|words| Value::from_words(dtype, &words)));
+            }
+            Access::Write(_, reply) => {
+                let _ = reply.send(answer.map(|_| IntfResult::Success));
+            }
+        }
+    }
+}
+
+enum Link<INTF: PhysicalInterface, const N: usize> {
+    Config(Main<Config, INTF, N>),
+    Cyclic(Main<Cyclic, INTF, N>),
+}
+
+struct Shared<INTF: PhysicalInterface, const N: usize> {
+    /// Empty only if a state change panicked
+    link: Option<Link<INTF, N>>,
+    queue: VecDeque<Request>,
+    current: Option<Request>,
+}
+
+/// Handle to a Main shared between threads, e.g. a cyclic control thread and
+/// a user interface. Accesses are serialized. In config state, reads and
+/// writes are executed at once. In cyclic state, they are queued and carried
+/// by the config words of the following cycles with their subnode, one
+/// access at a time. Results are sent back through a channel
+pub struct SharedMain<INTF: PhysicalInterface, const N: usize = MAX_FRAME_SIZE> {
+    shared: Arc<Mutex<Shared<INTF, N>>>,
+}
+
+impl<INTF: PhysicalInterface, const N: usize> Clone for SharedMain<INTF, N> {
+    fn clone(&self) -> SharedMain<INTF, N> {
+        SharedMain {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<INTF, const N: usize> SharedMain<INTF, N>
+where
+    INTF: PhysicalInterface,
+{
+    fn lock(&self) -> MutexGuard<'_, Shared<INTF, N>> {
+        self.shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn submit(&self, request: Request) {
+        let mut shared = self.lock();
+        match shared.link.as_mut() {
+            Some(Link::Config(main)) => request.execute(main),
+            _ => shared.queue.push_back(request),
+        }
+    }
+
+    /// Reads a register as a value of the given type
+    pub fn read(&self, subnode: u8, add: u16, dtype: DataType) -> ReadReply {
+        let (reply, answer) = mpsc::channel();
+        self.submit(Request {
+            subnode,
+            add,
+            access: Access::Read(dtype, reply),
+        });
+        answer
+    }
+
+    /// Writes a value to a register
+    pub fn write(&self, subnode: u8, add: u16, value: Value) -> WriteReply {
+        let (reply, answer) = mpsc::channel();
+        self.submit(Request {
+            subnode,
+            add,
+            access: Access::Write(value, reply),
+        });
+        answer
+    }
+
+    /// Exchanges a cyclic frame, see [`Main::cycle`]. The first queued access
+    /// to the subnode is carried by its config words. Fails with
+    /// [`IntfError::InvalidParameter`] in config state
+    pub fn cycle(&self, subnode: u8, data: &[u16]) -> Result<Vec<u16>, IntfError> {
+        let mut guard = self.lock();
+        let shared = &mut *guard;
+        let Some(Link::Cyclic(main)) = shared.link.as_mut() else {
+            return Err(IntfError::InvalidParameter);
+        };
+
+        if shared.current.is_none() {
+            let next = shared
+                .queue
+                .iter()
+                .position(|queued| queued.subnode == subnode);
+            if let Some(request) = next.and_then(|index| shared.queue.remove(index)) {
+                match request.start(main) {
+                    Ok(_) => shared.current = Some(request),
+                    Err(e) => request.complete(Err(e)),
+                }
+            }
+        }
+
+        let result = main.cycle(subnode, data);
+
+        if let Some(answer) = main.access_result() {
+            if let Some(request) = shared.current.take() {
+                request.complete(answer);
+            }
+        }
+        result
+    }
+
+    /// Requests the node to move to cyclic state. Does nothing if it already
+    /// is
+    pub fn into_cyclic(&self) -> Result<IntfResult, IntfError> {
+        let mut shared = self.lock();
+        match shared.link.take() {
+            Some(Link::Config(main)) => match main.into_cyclic() {
+                Ok(main) => {
+                    shared.link = Some(Link::Cyclic(main));
+                    Ok(IntfResult::Success)
+                }
+                Err((main, e)) => {
+                    shared.link = Some(Link::Config(main));
+                    Err(e)
+                }
+            },
+            Some(link) => {
+                shared.link = Some(link);
+                Ok(IntfResult::Success)
+            }
+            None => Err(IntfError::Interface),
+        }
+    }
+
+    /// Requests the node to move back to config state. Does nothing if it
+    /// already is. The queued accesses are executed right after
+    pub fn into_config(&self) -> Result<IntfResult, IntfError> {
+        let mut guard = self.lock();
+        let shared = &mut *guard;
+        match shared.link.take() {
+            Some(Link::Cyclic(main)) => match main.into_config() {
+                Ok(mut main) => {
+                    if let Some(request) = shared.current.take() {
+                        shared.queue.push_front(request);
+                    }
+                    while let Some(request) = shared.queue.pop_front() {
+                        request.execute(&mut main);
+                    }
+                    shared.link = Some(Link::Config(main));
+                    Ok(IntfResult::Success)
+                }
+                Err((main, e)) => {
+                    shared.link = Some(Link::Cyclic(main));
+                    Err(e)
+                }
+            },
+            Some(link) => {
+                shared.link = Some(link);
+                Ok(IntfResult::Success)
+            }
+            None => Err(IntfError::Interface),
+        }
+    }
+
+    pub fn is_cyclic(&self) -> bool {
+        matches!(self.lock().link, Some(Link::Cyclic(_)))
+    }
+
+    /// Number of accesses waiting for a cycle with their subnode
+    pub fn pending(&self) -> usize {
+        let shared = self.lock();
+        shared.queue.len() + shared.current.iter().count()
+    }
+}
+
+pub fn create_shared_main<INTF: PhysicalInterface, const N: usize>(
+    main: Main<Config, INTF, N>,
+) -> SharedMain<INTF, N> {
+    SharedMain {
+        shared: Arc::new(Mutex::new(Shared {
+            link: Some(Link::Config(main)),
+            queue: VecDeque::new(),
+            current: None,
+        })),
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Upper bounds of the latency buckets. The last bucket holds the latencies
+/// above the last bound
+pub const LATENCY_BOUNDS: [Duration; 7] = [
+    Duration::from_micros(50),
+    Duration::from_micros(100),
+    Duration::from_micros(200),
+    Duration::from_micros(500),
+    Duration::from_millis(1),
+    Duration::from_millis(2),
+    Duration::from_millis(5),
+];
+
+/// Histogram of the round trip time of the accesses
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Number of round trips per bucket, see [`LATENCY_BOUNDS`]
+    pub buckets: [u64; LATENCY_BOUNDS.len() + 1],
+    pub count: u64,
+    pub total: Duration,
+    pub min: Option<Duration>,
+    pub max: Option<Duration>,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BOUNDS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BOUNDS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total += latency;
+        self.min = Some(self.min.map_or(latency, |min| min.min(latency)));
+        self.max = Some(self.max.map_or(latency, |max| max.max(latency)));
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count {
+            0 => None,
+            count => Some(Duration::from_nanos(
+                (self.total.as_nanos() / count as u128) as u64,
+            )),
+        }
+    }
+}
+
+/// Counters of the traffic of a Main or a Node. Errors are counted where
+/// they are detected: a Main counts the error frames it receives, a Node the
+/// ones it sends
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    pub frames_sent: u64,
+    pub frames_received: u64,
+    pub crc_errors: u64,
+    pub payload_crc_errors: u64,
+    /// Received frames longer than the frame size, dropped
+    pub oversized_frames: u64,
+    /// Frames from or to a subnode other than the expected ones
+    pub subnode_mismatches: u64,
+    /// Frames whose command or address do not answer the request
+    pub wrong_commands: u64,
+    /// Number of error frames by error code
+    pub access_errors: BTreeMap<u32, u64>,
+    pub retries: u64,
+    pub timeouts: u64,
+    /// Round trips of the Main accesses. Nodes do not measure latency
+    pub latency: LatencyHistogram,
+}
+
+impl Statistics {
+    pub(crate) fn access_error(&mut self, code: u32) {
+        *self.access_errors.entry(code).or_insert(0) += 1;
+    }
+}
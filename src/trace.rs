@@ -0,0 +1,127 @@
+use crate::IntfError;
+
+#[cfg(any(feature = "log", feature = "tracing", feature = "defmt"))]
+use std::time::Instant;
+
+/// Instrumentation of a single transaction. Emits the subnode, address,
+/// command, result and duration of the transaction to the enabled backends
+/// (`log`, `tracing` and `defmt` features). Without any of them it is empty
+/// and compiled out
+#[cfg(any(feature = "log", feature = "tracing", feature = "defmt"))]
+// The span already holds the target when `tracing` is the only backend
+#[cfg_attr(not(any(feature = "log", feature = "defmt")), allow(dead_code))]
+pub(crate) struct Transaction {
+    kind: &'static str,
+    subnode: u8,
+    address: u16,
+    command: u16,
+    start: Instant,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+#[cfg(any(feature = "log", feature = "tracing", feature = "defmt"))]
+impl Transaction {
+    pub(crate) fn begin(
+        kind: &'static str,
+        subnode: u8,
+        address: u16,
+        command: u16,
+    ) -> Transaction {
+        Transaction {
+            kind,
+            subnode,
+            address,
+            command,
+            start: Instant::now(),
+            #[cfg(feature = "tracing")]
+            _span: tracing::debug_span!("mcb", kind, subnode, address, command).entered(),
+        }
+    }
+
+    pub(crate) fn end<T>(self, result: &Result<T, IntfError>) {
+        let elapsed_us = self.start.elapsed().as_micros() as u64;
+        let error = result.as_ref().err();
+
+        #[cfg(feature = "log")]
+        match error {
+            None => log::debug!(
+                "{} subnode {} address {:#05x} command {:#x}: ok in {} us",
+                self.kind,
+                self.subnode,
+                self.address,
+                self.command,
+                elapsed_us
+            ),
+            Some(e) => log::warn!(
+                "{} subnode {} address {:#05x} command {:#x}: {:?} in {} us",
+                self.kind,
+                self.subnode,
+                self.address,
+                self.command,
+                e,
+                elapsed_us
+            ),
+        }
+
+        #[cfg(feature = "tracing")]
+        match error {
+            None => tracing::debug!(elapsed_us, "ok"),
+            Some(e) => tracing::warn!(elapsed_us, error = ?e, "failed"),
+        }
+
+        #[cfg(feature = "defmt")]
+        match error {
+            None => defmt::debug!(
+                "{=str} subnode {=u8} address {=u16:#x} command {=u16:#x}: ok in {=u64} us",
+                self.kind,
+                self.subnode,
+                self.address,
+                self.command,
+                elapsed_us
+            ),
+            Some(e) => defmt::warn!(
+                "{=str} subnode {=u8} address {=u16:#x} command {=u16:#x}: {} in {=u64} us",
+                self.kind,
+                self.subnode,
+                self.address,
+                self.command,
+                e,
+                elapsed_us
+            ),
+        }
+    }
+}
+
+/// Instrumentation of a single transaction. No backend is enabled
+#[cfg(not(any(feature = "log", feature = "tracing", feature = "defmt")))]
+pub(crate) struct Transaction;
+
+#[cfg(not(any(feature = "log", feature = "tracing", feature = "defmt")))]
+impl Transaction {
+    #[inline(always)]
+    pub(crate) fn begin(
+        _kind: &'static str,
+        _subnode: u8,
+        _address: u16,
+        _command: u16,
+    ) -> Transaction {
+        Transaction
+    }
+
+    #[inline(always)]
+    pub(crate) fn end<T>(self, _result: &Result<T, IntfError>) {}
+}
+
+/// Reports an access repeated after a lost or corrupted answer
+#[inline(always)]
+pub(crate) fn retry(attempt: u32, error: &IntfError) {
+    #[cfg(feature = "log")]
+    log::debug!("retry {} after {:?}", attempt, error);
+    #[cfg(feature = "tracing")]
+    tracing::debug!(attempt, error = ?error, "retry");
+    #[cfg(feature = "defmt")]
+    defmt::debug!("retry {=u32} after {}", attempt, error);
+    #[cfg(not(any(feature = "log", feature = "tracing", feature = "defmt")))]
+    let _ = (attempt, error);
+}
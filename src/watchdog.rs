@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+/// State of the cyclic watchdog of a Node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchdogState {
+    /// No cycle timeout is configured
+    Disabled,
+    /// The last cyclic frame arrived within the cycle timeout
+    Armed,
+    /// No cyclic frame arrived within the cycle timeout. The Node should
+    /// bring the drive to a safe state
+    Expired,
+}
+
+type ExpiryCallback = Box<dyn FnMut() + Send>;
+
+/// Cycle timeout of a Node. Fed by every valid cyclic frame, it expires when
+/// the Main stops talking for longer than the timeout. Expiry is noticed when
+/// the Node checks it, not by a timer
+pub(crate) struct Watchdog {
+    timeout: Option<Duration>,
+    last_feed: Instant,
+    expired: bool,
+    on_expiry: Option<ExpiryCallback>,
+}
+
+impl Watchdog {
+    pub(crate) fn new() -> Watchdog {
+        Watchdog {
+            timeout: None,
+            last_feed: Instant::now(),
+            expired: false,
+            on_expiry: None,
+        }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+        self.feed();
+    }
+
+    pub(crate) fn set_callback(&mut self, callback: ExpiryCallback) {
+        self.on_expiry = Some(callback);
+    }
+
+    pub(crate) fn feed(&mut self) {
+        self.last_feed = Instant::now();
+        self.expired = false;
+    }
+
+    /// Checks the timeout and calls the callback once per expiry
+    pub(crate) fn check(&mut self) -> WatchdogState {
+        let Some(timeout) = self.timeout else {
+            return WatchdogState::Disabled;
+        };
+
+        if !self.expired && self.last_feed.elapsed() > timeout {
+            self.expired = true;
+            if let Some(callback) = self.on_expiry.as_mut() {
+                callback();
+            }
+        }
+
+        match self.expired {
+            true => WatchdogState::Expired,
+            false => WatchdogState::Armed,
+        }
+    }
+}
@@ -1,11 +1,35 @@
-use mcb::mcb_main::{create_main_mcb, Main};
-use mcb::mcb_node::{create_node_mcb, CommandType, Node, Request};
-use mcb::{Config, ExtMode, Init, IntfError, IntfResult, PhysicalInterface, MAX_FRAME_SIZE};
+use mcb::bus::{create_bus, BusAxis};
+use mcb::capture::{Capture, Direction, Recorder, Replay, PCAPNG_LINKTYPE};
+use mcb::checksum::{BitwiseCrc, CrcEngine, CrcUnit, HardwareCrc, TableCrc};
+use mcb::cia402::{OperationMode, State};
+use mcb::codec::{bytes_to_words, decode_str, words_to_bytes, ByteOrder};
+use mcb::disturbance::{DisturbanceConfig, DISTURBANCE_SUBNODE};
+use mcb::frame::{Frame, FrameCommand, FrameProblem};
+use mcb::gateway::{create_gateway, ERR_DRIVE_UNREACHABLE, ERR_STATE_CHANGE};
+use mcb::mcb_main::{create_main_mcb, create_sized_main_mcb, Main};
+use mcb::mcb_node::{
+    create_node_mcb, create_sized_node_mcb, CommandType, Dispatcher, Node, Request, ERR_NO_HANDLER,
+};
+use mcb::monitoring::{Channel, MonitoringConfig, TriggerMode, MONITORING_SUBNODE};
+use mcb::motion::{Homing, ProfileMove};
+use mcb::parameters::{Access, Parameter, ParameterSet, RegisterDescription, Value};
+use mcb::scheduler::{create_scheduler, CycleStatistics, Timer};
+use mcb::shared::create_shared_main;
+use mcb::stats::{Statistics, LATENCY_BOUNDS};
+use mcb::watchdog::WatchdogState;
+use mcb::{
+    Config, Cyclic, DataType, ExtMode, Init, IntfError, IntfResult, PhysicalInterface, Samples,
+    TargetState, MAX_FRAME_SIZE,
+};
 
 use mcb::IntfResult::*;
 
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::mpsc::RecvError;
 use std::thread;
+use std::time::Duration;
 use std::{sync::mpsc, sync::mpsc::Receiver, sync::mpsc::Sender};
 
 use float_eq::assert_float_eq;
@@ -73,9 +97,9 @@ fn create_wrongmain_node_thread<T>() -> (NodeThread<T>, MainThreadWrongCRC<T>) {
     )
 }
 
-impl PhysicalInterface for NodeThread<[u16; MAX_FRAME_SIZE]> {
+impl<const M: usize> PhysicalInterface for NodeThread<[u16; M]> {
     fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
-        let mut msg = [0u16; MAX_FRAME_SIZE];
+        let mut msg = [0u16; M];
 
         msg[..frame.len()].copy_from_slice(frame);
 
@@ -91,9 +115,9 @@ impl PhysicalInterface for NodeThread<[u16; MAX_FRAME_SIZE]> {
     }
 }
 
-impl PhysicalInterface for MainThread<[u16; MAX_FRAME_SIZE]> {
+impl<const M: usize> PhysicalInterface for MainThread<[u16; M]> {
     fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
-        let mut msg = [0u16; MAX_FRAME_SIZE];
+        let mut msg = [0u16; M];
 
         msg[..frame.len()].copy_from_slice(frame);
 
@@ -205,6 +229,7 @@ fn init_wrong_node(
     mcb_node_test.init()
 }
 
+#[allow(clippy::while_let_loop)]
 fn get_request(
     node_cfg: &mut Node<Config, NodeThread<[u16; MAX_FRAME_SIZE]>>,
 ) -> Result<Request, IntfError> {
@@ -231,6 +256,7 @@ fn get_request(
     Ok(request)
 }
 
+#[allow(clippy::while_let_loop)]
 fn get_wrong_request(
     node_cfg: &mut Node<Config, NodeThreadWrongCRC<[u16; MAX_FRAME_SIZE]>>,
 ) -> Result<Request, IntfError> {
@@ -256,7 +282,40 @@ fn get_wrong_request(
     Ok(request)
 }
 
+fn init_subnode_node(
+    node_thread: NodeThread<[u16; MAX_FRAME_SIZE]>,
+    mode: ExtMode,
+    subnode: u8,
+) -> Node<Config, NodeThread<[u16; MAX_FRAME_SIZE]>> {
+    let mcb_node_test: Node<Init, NodeThread<[u16; MAX_FRAME_SIZE]>> =
+        create_node_mcb(Some(node_thread), mode, subnode);
+
+    mcb_node_test.init()
+}
+
+fn accept_into_cyclic(
+    mut node_cfg: Node<Config, NodeThread<[u16; MAX_FRAME_SIZE]>>,
+) -> Node<Cyclic, NodeThread<[u16; MAX_FRAME_SIZE]>> {
+    let request = node_cfg.read().unwrap();
+    let Ok(node_cyc) = node_cfg.into_cyclic(&request) else {
+        panic!("Something wrong");
+    };
+    node_cyc
+}
+
+fn serve_requests<F, const M: usize>(
+    mut node_cfg: Node<Config, NodeThread<[u16; M]>, M>,
+    mut handler: F,
+) where
+    F: FnMut(&mut Node<Config, NodeThread<[u16; M]>, M>, Request),
+{
+    while let Ok(request) = node_cfg.read() {
+        handler(&mut node_cfg, request);
+    }
+}
+
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_u8() {
     const ADDRESS: u16 = 10u16;
     const DATA: u8 = 0xA5u8;
@@ -326,6 +385,7 @@ fn test_std_write_u8() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_u16() {
     const ADDRESS: u16 = 10u16;
     const DATA: u16 = 0xA5A5u16;
@@ -396,6 +456,7 @@ fn test_std_write_u16() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_u32() {
     const ADDRESS: u16 = 10u16;
     const DATA: u32 = 0xA5A5A5A5u32;
@@ -465,6 +526,7 @@ fn test_std_write_u32() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_u64() {
     const ADDRESS: u16 = 10u16;
     const DATA: u64 = 0xA5A5A5A5A5A5A5A5u64;
@@ -534,6 +596,7 @@ fn test_std_write_u64() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_i8() {
     const ADDRESS: u16 = 10u16;
     const DATA: i8 = 0xA5u8 as i8;
@@ -603,6 +666,7 @@ fn test_std_write_i8() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_i16() {
     const ADDRESS: u16 = 10u16;
     const DATA: i16 = 0xA5A5u16 as i16;
@@ -673,6 +737,7 @@ fn test_std_write_i16() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_i32() {
     const ADDRESS: u16 = 10u16;
     const DATA: i32 = 0xA5A5A5A5u32 as i32;
@@ -742,6 +807,7 @@ fn test_std_write_i32() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_i64() {
     const ADDRESS: u16 = 10u16;
     const DATA: i64 = 0xA5A5A5A5A5A5A5A5u64 as i64;
@@ -811,6 +877,7 @@ fn test_std_write_i64() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro, clippy::unnecessary_cast)]
 fn test_std_read_f32() {
     const ADDRESS: u16 = 10u16;
     const DATA: f32 = 1.0 as f32;
@@ -845,6 +912,7 @@ fn test_std_read_f32() {
 }
 
 #[test]
+#[allow(clippy::unnecessary_cast)]
 fn test_std_write_f32() {
     const ADDRESS: u16 = 10u16;
     const DATA: f32 = 1.0 as f32;
@@ -880,6 +948,7 @@ fn test_std_write_f32() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro, clippy::unnecessary_cast)]
 fn test_std_read_f64() {
     const ADDRESS: u16 = 10u16;
     const DATA: f64 = 1.0 as f64;
@@ -914,6 +983,7 @@ fn test_std_read_f64() {
 }
 
 #[test]
+#[allow(clippy::unnecessary_cast)]
 fn test_std_write_f64() {
     const ADDRESS: u16 = 10u16;
     const DATA: f64 = 1.0 as f64;
@@ -948,6 +1018,56 @@ fn test_std_write_f64() {
     assert!(matches!(result, Ok(IntfResult::Success)));
 }
 
+#[test]
+fn test_std_float_round_trip() {
+    const F32_ADDRESS: u16 = 10u16;
+    const F64_ADDRESS: u16 = 11u16;
+    const F32_DATA: f32 = 1.5;
+    const F64_DATA: f64 = -2.25;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        serve_requests(init_node(node_thread), |node_cfg, request| {
+            let _ = match request.command {
+                CommandType::Write
+                    if request.address == F32_ADDRESS
+                        && node_cfg.get_data_f32(&request) == F32_DATA =>
+                {
+                    node_cfg.ack(request.address)
+                }
+                CommandType::Write
+                    if request.address == F64_ADDRESS
+                        && node_cfg.get_data_f64(&request) == F64_DATA =>
+                {
+                    node_cfg.ack(request.address)
+                }
+                CommandType::Read if request.address == F32_ADDRESS => {
+                    node_cfg.write_f32(request.address, F32_DATA)
+                }
+                CommandType::Read if request.address == F64_ADDRESS => {
+                    node_cfg.write_f64(request.address, F64_DATA)
+                }
+                _ => node_cfg.error(request.address, 0x0u32),
+            };
+        });
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let result = mcb_main_cfg.write_f32(NODE_SUBNODE, F32_ADDRESS, F32_DATA);
+    assert!(matches!(result, Ok(IntfResult::Success)));
+    let result = mcb_main_cfg.write_f64(NODE_SUBNODE, F64_ADDRESS, F64_DATA);
+    assert!(matches!(result, Ok(IntfResult::Success)));
+
+    assert_eq!(
+        mcb_main_cfg.read_f32(NODE_SUBNODE, F32_ADDRESS).unwrap(),
+        F32_DATA
+    );
+    assert_eq!(
+        mcb_main_cfg.read_f64(NODE_SUBNODE, F64_ADDRESS).unwrap(),
+        F64_DATA
+    );
+}
+
 #[test]
 fn test_main_write_out_of_index_address() {
     const ADDRESS: u16 = 0x1000u16;
@@ -971,6 +1091,7 @@ fn test_main_read_out_of_index_address() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_main_write_unexistent_register() {
     const ADDRESS: u16 = 0x0100u16;
     let (node_thread, main_thread) = create_mainnodethread();
@@ -1004,6 +1125,7 @@ fn test_main_write_unexistent_register() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_main_read_unexistent_register() {
     const ADDRESS: u16 = 0x0250u16;
     let (node_thread, main_thread) = create_mainnodethread();
@@ -1037,6 +1159,7 @@ fn test_main_read_unexistent_register() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_main_wrong_node_crc() {
     const ADDRESS: u16 = 10u16;
     let (node_thread, main_thread) = create_main_wrongnode_thread();
@@ -1063,6 +1186,7 @@ fn test_main_wrong_node_crc() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_node_wrong_main_crc() {
     const ADDRESS: u16 = 10u16;
     let (node_thread, main_thread) = create_wrongmain_node_thread();
@@ -1201,6 +1325,7 @@ fn test_wrong_subnode_answer_read() {
 }
 
 #[test]
+#[allow(clippy::needless_borrow)]
 fn test_std_write_small_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "small";
@@ -1236,6 +1361,7 @@ fn test_std_write_small_str() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_small_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "small";
@@ -1270,6 +1396,7 @@ fn test_std_read_small_str() {
 }
 
 #[test]
+#[allow(clippy::needless_borrow)]
 fn test_std_write_big_extended_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "big_extended";
@@ -1305,6 +1432,7 @@ fn test_std_write_big_extended_str() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_big_extended_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "big_extended";
@@ -1339,6 +1467,7 @@ fn test_std_read_big_extended_str() {
 }
 
 #[test]
+#[allow(clippy::needless_borrow)]
 fn test_std_write_small_segmented_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "small";
@@ -1374,6 +1503,7 @@ fn test_std_write_small_segmented_str() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_small_segmented_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "small";
@@ -1408,6 +1538,7 @@ fn test_std_read_small_segmented_str() {
 }
 
 #[test]
+#[allow(clippy::needless_borrow)]
 fn test_std_write_big_segmented_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "big_segmented";
@@ -1443,6 +1574,7 @@ fn test_std_write_big_segmented_str() {
 }
 
 #[test]
+#[allow(clippy::match_like_matches_macro)]
 fn test_std_read_big_segmented_str() {
     const ADDRESS: u16 = 10u16;
     const DATA: &str = "big_segmented";
@@ -1475,3 +1607,2701 @@ fn test_std_read_big_segmented_str() {
 
     assert_eq!(result.unwrap(), DATA);
 }
+
+const MON_STATUS: u16 = 0x0C1;
+const MON_TRIGGER_TYPE: u16 = 0x0C2;
+const MON_WINDOW_SAMP: u16 = 0x0C4;
+const MON_TOTAL_MAP: u16 = 0x0D0;
+const MON_REG0_MAP: u16 = 0x0D1;
+const MON_DATA: u16 = 0x0B2;
+
+#[test]
+fn test_monitoring_extended() {
+    let config = MonitoringConfig {
+        channels: vec![
+            Channel {
+                subnode: 1,
+                address: 0x030,
+                dtype: DataType::U16,
+            },
+            Channel {
+                subnode: 1,
+                address: 0x031,
+                dtype: DataType::I32,
+            },
+        ],
+        trigger: TriggerMode::Forced,
+        divisor: 10,
+        samples: 3,
+    };
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    let node = thread::spawn(move || {
+        let mut writes = Vec::new();
+        let node_cfg = init_subnode_node(node_thread, ExtMode::Extended, MONITORING_SUBNODE);
+        serve_requests(node_cfg, |node_cfg, request| match request.command {
+            CommandType::Read if request.address == MON_STATUS => {
+                let _ = node_cfg.write_u16(request.address, 0x0004);
+            }
+            CommandType::Read if request.address == MON_DATA => {
+                // Samples (1, -1), (2, -2), (3, -3) interleaved
+                let _ = node_cfg.write_words(
+                    request.address,
+                    &[1, 0xffff, 0xffff, 2, 0xfffe, 0xffff, 3, 0xfffd, 0xffff],
+                );
+            }
+            CommandType::Write => {
+                writes.push((request.address, node_cfg.get_data_u32(&request)));
+                let _ = node_cfg.ack(request.address);
+            }
+            _ => {
+                let _ = node_cfg.error(request.address, 0x0u32);
+            }
+        });
+        writes
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut monitoring = mcb_main_cfg.monitoring(&config);
+
+    assert!(matches!(monitoring.configure(), Ok(IntfResult::Success)));
+    assert!(matches!(monitoring.enable(), Ok(IntfResult::Success)));
+    assert!(matches!(monitoring.trigger(), Ok(IntfResult::Success)));
+    assert!(matches!(monitoring.is_ready(), Ok(true)));
+
+    let result = monitoring.fetch().unwrap();
+    assert_eq!(result[0], Samples::U16(vec![1, 2, 3]));
+    assert_eq!(result[1], Samples::I32(vec![-1, -2, -3]));
+
+    drop(mcb_main_cfg);
+    let writes = node.join().unwrap();
+    assert!(writes.contains(&(MON_REG0_MAP, 0x0002_1030)));
+    assert!(writes.contains(&(MON_REG0_MAP + 1, 0x0004_1031)));
+    assert!(writes.contains(&(MON_WINDOW_SAMP, 3)));
+
+    // 16 bit registers only use the lower word
+    let writes: Vec<(u16, u16)> = writes
+        .into_iter()
+        .map(|(address, value)| (address, value as u16))
+        .collect();
+    assert!(writes.contains(&(MON_TOTAL_MAP, 2)));
+    assert!(writes.contains(&(MON_TRIGGER_TYPE, 1)));
+}
+
+#[test]
+fn test_monitoring_segmented_fetch() {
+    let config = MonitoringConfig {
+        channels: vec![Channel {
+            subnode: 1,
+            address: 0x030,
+            dtype: DataType::U32,
+        }],
+        trigger: TriggerMode::Auto,
+        divisor: 1,
+        samples: 5,
+    };
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let node_cfg = init_subnode_node(node_thread, ExtMode::Segmented, MONITORING_SUBNODE);
+        serve_requests(node_cfg, |node_cfg, request| {
+            let _ = node_cfg.write_words(request.address, &[1, 0, 2, 0, 3, 0, 4, 0, 5, 0]);
+        });
+    });
+
+    let mut mcb_main_cfg = init_segmented_main(main_thread);
+    let result = mcb_main_cfg.monitoring(&config).fetch().unwrap();
+
+    assert_eq!(result, vec![Samples::U32(vec![1, 2, 3, 4, 5])]);
+}
+
+#[test]
+fn test_monitoring_invalid_config() {
+    let config = MonitoringConfig {
+        channels: vec![],
+        trigger: TriggerMode::Auto,
+        divisor: 1,
+        samples: 5,
+    };
+    let (_node_thread, main_thread) = create_mainnodethread();
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let result = mcb_main_cfg.monitoring(&config).configure();
+
+    assert!(matches!(result, Err(IntfError::InvalidParameter)));
+}
+
+const DIST_SAMPLES: u16 = 0x0F3;
+const DIST_DATA: u16 = 0x0B4;
+
+fn serve_disturbance(
+    node_thread: NodeThread<[u16; MAX_FRAME_SIZE]>,
+    mode: ExtMode,
+) -> thread::JoinHandle<Vec<(u16, Vec<u16>)>> {
+    thread::spawn(move || {
+        let mut writes = Vec::new();
+        let node_cfg = init_subnode_node(node_thread, mode, DISTURBANCE_SUBNODE);
+        serve_requests(node_cfg, |node_cfg, request| match request.command {
+            CommandType::Write | CommandType::ExtWrite => {
+                writes.push((request.address, node_cfg.get_data_words(&request).to_vec()));
+                let _ = node_cfg.ack(request.address);
+            }
+            _ => {
+                let _ = node_cfg.error(request.address, 0x0u32);
+            }
+        });
+        writes
+    })
+}
+
+#[test]
+fn test_disturbance_extended_upload() {
+    let config = DisturbanceConfig {
+        channels: vec![
+            Channel {
+                subnode: 1,
+                address: 0x01A,
+                dtype: DataType::I16,
+            },
+            Channel {
+                subnode: 1,
+                address: 0x01B,
+                dtype: DataType::F32,
+            },
+        ],
+        divisor: 1,
+    };
+    let (node_thread, main_thread) = create_mainnodethread();
+    let node = serve_disturbance(node_thread, ExtMode::Extended);
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut disturbance = mcb_main_cfg.disturbance(&config);
+
+    assert!(matches!(disturbance.configure(), Ok(IntfResult::Success)));
+    let result = disturbance.upload(&[
+        Samples::I16(vec![-1, 0, 1]),
+        Samples::F32(vec![0.5, 1.0, -2.0]),
+    ]);
+    assert!(matches!(result, Ok(IntfResult::Success)));
+    assert!(matches!(disturbance.start(), Ok(IntfResult::Success)));
+
+    drop(mcb_main_cfg);
+    let writes = node.join().unwrap();
+    let (_, length) = writes
+        .iter()
+        .find(|(address, _)| *address == DIST_SAMPLES)
+        .unwrap();
+    assert_eq!(length[..2], [3, 0]);
+
+    let (_, data) = writes
+        .iter()
+        .find(|(address, _)| *address == DIST_DATA)
+        .unwrap();
+    assert_eq!(
+        data[..],
+        [0xffff, 0x0000, 0x3f00, 0x0000, 0x0000, 0x3f80, 0x0001, 0x0000, 0xc000]
+    );
+}
+
+#[test]
+fn test_disturbance_segmented_upload() {
+    let config = DisturbanceConfig {
+        channels: vec![Channel {
+            subnode: 1,
+            address: 0x01A,
+            dtype: DataType::U16,
+        }],
+        divisor: 1,
+    };
+    let (node_thread, main_thread) = create_mainnodethread();
+    let node = serve_disturbance(node_thread, ExtMode::Segmented);
+
+    let mut mcb_main_cfg = init_segmented_main(main_thread);
+    let result = mcb_main_cfg
+        .disturbance(&config)
+        .upload(&[Samples::U16(vec![1, 2, 3, 4, 5, 6])]);
+    assert!(matches!(result, Ok(IntfResult::Success)));
+
+    drop(mcb_main_cfg);
+    let writes = node.join().unwrap();
+    let (_, data) = writes
+        .iter()
+        .find(|(address, _)| *address == DIST_DATA)
+        .unwrap();
+    assert_eq!(data[..6], [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn test_disturbance_wrong_samples() {
+    let config = DisturbanceConfig {
+        channels: vec![Channel {
+            subnode: 1,
+            address: 0x01A,
+            dtype: DataType::U16,
+        }],
+        divisor: 1,
+    };
+    let (_node_thread, main_thread) = create_mainnodethread();
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let result = mcb_main_cfg
+        .disturbance(&config)
+        .upload(&[Samples::I32(vec![1, 2, 3])]);
+
+    assert!(matches!(result, Err(IntfError::InvalidParameter)));
+}
+
+const DRV_STATE_CONTROL: u16 = 0x010;
+const DRV_STATE_STATUS: u16 = 0x011;
+const DRV_OP_CMD: u16 = 0x014;
+const DRV_OP_VALUE: u16 = 0x015;
+const TARGET_POSITION: u16 = 0x020;
+const TARGET_VELOCITY: u16 = 0x021;
+const HOMING_METHOD: u16 = 0x028;
+const HOMING_OFFSET: u16 = 0x02B;
+const ACTUAL_POSITION: u16 = 0x030;
+const ACTUAL_VELOCITY: u16 = 0x031;
+
+/// Drive emulating the CiA-402 power stage state machine and the motion
+/// commands. Any other register is stored and returned as is
+struct SimulatedDrive {
+    status: u16,
+    registers: HashMap<u16, u64>,
+}
+
+impl SimulatedDrive {
+    fn new(status: u16) -> SimulatedDrive {
+        SimulatedDrive {
+            status,
+            registers: HashMap::new(),
+        }
+    }
+
+    fn control(&mut self, control: u16) {
+        let state = State::from_status_word(self.status);
+        self.status = match (state, control) {
+            (State::Fault, c) if c & 0x0080 != 0 => 0x0040,
+            (State::Fault, _) => self.status,
+            (_, c) if c & 0x0002 == 0 => 0x0040,
+            (State::OperationEnabled, c) if c & 0x0004 == 0 => 0x0007,
+            (State::ReadyToSwitchOn | State::SwitchedOn, c) if c & 0x0004 == 0 => 0x0040,
+            (State::SwitchOnDisabled | State::SwitchedOn | State::OperationEnabled, 0x0006) => {
+                0x0021
+            }
+            (State::ReadyToSwitchOn | State::OperationEnabled, 0x0007) => 0x0023,
+            (State::SwitchedOn | State::QuickStopActive, 0x000F) => 0x0027,
+            _ => self.status,
+        };
+
+        if State::from_status_word(self.status) == State::OperationEnabled {
+            self.motion(control);
+        }
+    }
+
+    /// Completes homing and profile position moves as soon as they start
+    fn motion(&mut self, control: u16) {
+        let mode = *self.registers.get(&DRV_OP_CMD).unwrap_or(&0) as i8;
+        let position = *self.registers.get(&ACTUAL_POSITION).unwrap_or(&0) as i32;
+
+        if control & 0x0010 == 0 {
+            if mode == 1 {
+                self.status &= !0x1000;
+            }
+            return;
+        }
+
+        match mode {
+            1 => {
+                let target = *self.registers.get(&TARGET_POSITION).unwrap_or(&0) as i32;
+                let position = if control & 0x0040 != 0 {
+                    position + target
+                } else {
+                    target
+                };
+                self.registers
+                    .insert(ACTUAL_POSITION, position as u32 as u64);
+                self.status |= 0x1400;
+            }
+            6 => {
+                if *self.registers.get(&HOMING_METHOD).unwrap_or(&0) as i8 <= 0 {
+                    self.status |= 0x2000;
+                } else {
+                    let offset = *self.registers.get(&HOMING_OFFSET).unwrap_or(&0) as i32;
+                    self.registers.insert(ACTUAL_POSITION, offset as u32 as u64);
+                    self.status |= 0x1400;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn serve<const M: usize>(mut self, node_cfg: Node<Config, NodeThread<[u16; M]>, M>) -> Self {
+        serve_requests(node_cfg, |node_cfg, request| match request.command {
+            CommandType::Read if request.address == DRV_STATE_STATUS => {
+                let _ = node_cfg.write_u16(request.address, self.status);
+            }
+            CommandType::Read if request.address == DRV_OP_VALUE => {
+                let mode = *self.registers.get(&DRV_OP_CMD).unwrap_or(&0);
+                let _ = node_cfg.write_u64(request.address, mode);
+            }
+            CommandType::Read if request.address == ACTUAL_VELOCITY => {
+                let velocity = *self.registers.get(&TARGET_VELOCITY).unwrap_or(&0);
+                let _ = node_cfg.write_u64(request.address, velocity);
+            }
+            CommandType::Read => {
+                let value = *self.registers.get(&request.address).unwrap_or(&0);
+                let _ = node_cfg.write_u64(request.address, value);
+            }
+            CommandType::Write if request.address == DRV_STATE_CONTROL => {
+                self.control(node_cfg.get_data_u16(&request));
+                let _ = node_cfg.ack(request.address);
+            }
+            CommandType::Write => {
+                self.registers
+                    .insert(request.address, node_cfg.get_data_u64(&request));
+                let _ = node_cfg.ack(request.address);
+            }
+            _ => {
+                let _ = node_cfg.error(request.address, 0x0u32);
+            }
+        });
+        self
+    }
+}
+
+fn spawn_drive(
+    node_thread: NodeThread<[u16; MAX_FRAME_SIZE]>,
+    drive: SimulatedDrive,
+) -> thread::JoinHandle<SimulatedDrive> {
+    thread::spawn(move || drive.serve(init_node(node_thread)))
+}
+
+#[test]
+fn test_cia402_enable_disable() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let drive = spawn_drive(node_thread, SimulatedDrive::new(0x0040));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut cia402 = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    assert!(matches!(cia402.state(), Ok(State::SwitchOnDisabled)));
+    assert!(matches!(cia402.enable(), Ok(IntfResult::Success)));
+    assert!(matches!(cia402.state(), Ok(State::OperationEnabled)));
+    assert!(matches!(cia402.disable(), Ok(IntfResult::Success)));
+    assert!(matches!(cia402.state(), Ok(State::SwitchOnDisabled)));
+
+    drop(mcb_main_cfg);
+    assert_eq!(drive.join().unwrap().status, 0x0040);
+}
+
+#[test]
+fn test_cia402_quick_stop_and_fault_reset() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let drive = spawn_drive(node_thread, SimulatedDrive::new(0x0027));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut cia402 = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    assert!(matches!(cia402.quick_stop(), Ok(IntfResult::Success)));
+    assert!(matches!(cia402.state(), Ok(State::QuickStopActive)));
+    assert!(matches!(cia402.enable(), Ok(IntfResult::Success)));
+
+    drop(mcb_main_cfg);
+    drive.join().unwrap();
+
+    let (node_thread, main_thread) = create_mainnodethread();
+    let drive = spawn_drive(node_thread, SimulatedDrive::new(0x0008));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut cia402 = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    assert!(matches!(cia402.state(), Ok(State::Fault)));
+    assert!(matches!(cia402.fault_reset(), Ok(IntfResult::Success)));
+    assert!(matches!(cia402.state(), Ok(State::SwitchOnDisabled)));
+
+    drop(mcb_main_cfg);
+    drive.join().unwrap();
+}
+
+#[test]
+fn test_cia402_enable_timeout_in_fault() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let _drive = spawn_drive(node_thread, SimulatedDrive::new(0x0008));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut cia402 = mcb_main_cfg.cia402(NODE_SUBNODE);
+    cia402.set_timeout(Duration::from_millis(20));
+
+    assert!(matches!(cia402.enable(), Err(IntfError::Timeout)));
+}
+
+#[test]
+fn test_cia402_operation_mode() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let _drive = spawn_drive(node_thread, SimulatedDrive::new(0x0040));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut cia402 = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    let result = cia402.set_operation_mode(OperationMode::ProfileVelocity);
+    assert!(matches!(result, Ok(IntfResult::Success)));
+    assert!(matches!(
+        cia402.operation_mode(),
+        Ok(OperationMode::ProfileVelocity)
+    ));
+}
+
+#[test]
+fn test_motion_homing() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let _drive = spawn_drive(node_thread, SimulatedDrive::new(0x0027));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut axis = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    let homing = Homing {
+        method: 35,
+        speed: 100,
+        acceleration: 1000,
+        offset: 250,
+    };
+    assert!(matches!(axis.home(&homing), Ok(IntfResult::Success)));
+    assert!(matches!(axis.operation_mode(), Ok(OperationMode::Homing)));
+    assert!(matches!(axis.position(), Ok(250)));
+
+    let homing = Homing {
+        method: -1,
+        ..homing
+    };
+    assert!(matches!(axis.home(&homing), Err(IntfError::Motion(status)) if status & 0x2000 != 0));
+}
+
+#[test]
+fn test_motion_homing_sized_frames() {
+    const FRAME_SIZE: usize = 16;
+    let (node_thread, main_thread) = create_mainnodethread();
+    thread::spawn(move || {
+        let mcb_node_test: Node<Init, NodeThread<[u16; FRAME_SIZE]>, FRAME_SIZE> =
+            create_sized_node_mcb(Some(node_thread), ExtMode::Extended, NODE_SUBNODE);
+        SimulatedDrive::new(0x0027).serve(mcb_node_test.init())
+    });
+
+    let mcb_main_test: Main<Init, MainThread<[u16; FRAME_SIZE]>, FRAME_SIZE> =
+        create_sized_main_mcb(Some(main_thread), ExtMode::Extended, NODE_SUBNODE);
+    let mut mcb_main_cfg = mcb_main_test.init();
+    let mut axis = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    let homing = Homing {
+        method: 35,
+        speed: 100,
+        acceleration: 1000,
+        offset: 250,
+    };
+    assert!(matches!(axis.home(&homing), Ok(IntfResult::Success)));
+    assert!(matches!(axis.position(), Ok(250)));
+}
+
+#[test]
+fn test_motion_profile_position() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let _drive = spawn_drive(node_thread, SimulatedDrive::new(0x0027));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut axis = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    let profile = ProfileMove {
+        target: 1000,
+        velocity: 100,
+        acceleration: 1000,
+        deceleration: 1000,
+        relative: false,
+    };
+    assert!(matches!(axis.move_to(&profile), Ok(IntfResult::Success)));
+    assert!(matches!(axis.position(), Ok(1000)));
+
+    let profile = ProfileMove {
+        target: -1500,
+        relative: true,
+        ..profile
+    };
+    assert!(matches!(axis.move_to(&profile), Ok(IntfResult::Success)));
+    assert!(matches!(axis.position(), Ok(-500)));
+}
+
+#[test]
+fn test_motion_profile_velocity() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let _drive = spawn_drive(node_thread, SimulatedDrive::new(0x0027));
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let mut axis = mcb_main_cfg.cia402(NODE_SUBNODE);
+
+    assert!(matches!(
+        axis.set_velocity(-300, 1000),
+        Ok(IntfResult::Success)
+    ));
+    assert!(matches!(
+        axis.operation_mode(),
+        Ok(OperationMode::ProfileVelocity)
+    ));
+    assert!(matches!(axis.velocity(), Ok(-300)));
+    assert!(matches!(axis.state(), Ok(State::OperationEnabled)));
+}
+
+#[test]
+fn test_bus_broadcast_errors_per_axis() {
+    const ADDRESS: u16 = 10u16;
+    let mut axes = Vec::new();
+
+    for axis in 0..3u16 {
+        let (node_thread, main_thread) = create_mainnodethread();
+        thread::spawn(move || {
+            serve_requests(init_node(node_thread), |node_cfg, request| {
+                if axis == 1 {
+                    let _ = node_cfg.error(request.address, 0x1234u32);
+                } else if node_cfg.get_data_u16(&request) == axis {
+                    let _ = node_cfg.ack(request.address);
+                }
+            });
+        });
+        axes.push(create_main_mcb(
+            Some(main_thread),
+            ExtMode::Extended,
+            MAIN_SUBNODE,
+        ));
+    }
+
+    let mut bus = create_bus(axes).init();
+    assert_eq!(bus.len(), 3);
+
+    let result = bus.broadcast(|index, axis| axis.write_u16(NODE_SUBNODE, ADDRESS, index as u16));
+    let errors = result.unwrap_err().errors;
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], (1, IntfError::Access(0x1234u32))));
+}
+
+#[test]
+fn test_bus_cycle() {
+    let mut axes = Vec::new();
+
+    for axis in 0..2u16 {
+        let subnode = NODE_SUBNODE + axis as u8;
+        let (node_thread, main_thread) = create_mainnodethread();
+        thread::spawn(move || {
+            let node_cfg = init_subnode_node(node_thread, ExtMode::Extended, subnode);
+            let mut node_cyc = accept_into_cyclic(node_cfg);
+            while let Ok(request) = node_cyc.read() {
+                let data: Vec<u16> = node_cyc
+                    .get_cyclic_data(&request, 2)
+                    .iter()
+                    .map(|value| value + axis)
+                    .collect();
+                let _ = node_cyc.write_cyclic(&data);
+            }
+        });
+        axes.push(create_main_mcb(
+            Some(main_thread),
+            ExtMode::Extended,
+            subnode,
+        ));
+    }
+
+    let Ok(mut bus) = create_bus(axes).init().into_cyclic() else {
+        panic!("Something wrong");
+    };
+    let result = bus.cycle(&[&[10, 20], &[30, 40]]).unwrap();
+
+    assert_eq!(result, vec![vec![10, 20], vec![31, 41]]);
+
+    let result = bus.cycle(&[&[10, 20]]);
+    assert!(matches!(
+        result.unwrap_err().errors[..],
+        [(1, IntfError::InvalidParameter)]
+    ));
+}
+
+fn init_state_change_main(
+    main_thread: MainThread<[u16; MAX_FRAME_SIZE]>,
+) -> Main<Config, MainThread<[u16; MAX_FRAME_SIZE]>> {
+    let mcb_main_test: Main<Init, MainThread<[u16; MAX_FRAME_SIZE]>> =
+        create_main_mcb(Some(main_thread), ExtMode::Extended, NODE_SUBNODE);
+
+    mcb_main_test.init()
+}
+
+#[test]
+fn test_state_change_handshake() {
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    let node = thread::spawn(move || {
+        let mut node_cyc = accept_into_cyclic(init_node(node_thread));
+
+        let request = node_cyc.read().unwrap();
+        if !matches!(request.command, CommandType::Idle) {
+            panic!("Something wrong");
+        }
+        let data = node_cyc.get_cyclic_data(&request, 1).to_vec();
+        let _ = node_cyc.write_cyclic(&data);
+
+        let request = node_cyc.read().unwrap();
+        if !matches!(
+            request.command,
+            CommandType::StateChange(TargetState::Config)
+        ) {
+            panic!("Something wrong");
+        }
+        node_cyc.into_config(&request).is_ok()
+    });
+
+    let mcb_main_cfg = init_state_change_main(main_thread);
+    let Ok(mut mcb_main_cyc) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+    assert_eq!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x1234]).unwrap(),
+        [0x1234]
+    );
+
+    assert!(mcb_main_cyc.into_config().is_ok());
+    assert!(node.join().unwrap());
+}
+
+#[test]
+fn test_state_change_rejected() {
+    const ADDRESS: u16 = 10u16;
+    const DATA: u8 = 0xA5u8;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        serve_requests(init_node(node_thread), |node_cfg, request| {
+            match request.command {
+                CommandType::StateChange(_) => {
+                    let _ = node_cfg.reject_state_change(0x0601_0000u32);
+                }
+                _ => {
+                    let _ = node_cfg.write_u8(request.address, DATA);
+                }
+            }
+        });
+    });
+
+    let mcb_main_cfg = init_state_change_main(main_thread);
+    let Err((mut mcb_main_cfg, error)) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+
+    assert!(matches!(error, IntfError::Access(0x0601_0000u32)));
+    assert!(matches!(
+        mcb_main_cfg.read_u8(NODE_SUBNODE, ADDRESS),
+        Ok(DATA)
+    ));
+}
+
+#[test]
+fn test_bus_into_cyclic_rollback() {
+    let mut axes = Vec::new();
+
+    for axis in 0..2u16 {
+        let (node_thread, main_thread) = create_mainnodethread();
+        thread::spawn(move || {
+            let mut node_cfg = init_node(node_thread);
+            if axis == 1 {
+                let _ = node_cfg.read();
+                let _ = node_cfg.reject_state_change(0x1u32);
+                return;
+            }
+
+            let mut node_cyc = accept_into_cyclic(node_cfg);
+            if let Ok(request) = node_cyc.read() {
+                let _ = node_cyc.into_config(&request);
+            }
+        });
+        axes.push(create_main_mcb(
+            Some(main_thread),
+            ExtMode::Extended,
+            NODE_SUBNODE,
+        ));
+    }
+
+    let Err(error) = create_bus(axes).init().into_cyclic() else {
+        panic!("Something wrong");
+    };
+
+    assert_eq!(error.errors.len(), 1);
+    assert!(matches!(error.errors[0], (1, IntfError::Access(0x1u32))));
+    let Ok(bus) = error.into_config_bus() else {
+        panic!("Something wrong");
+    };
+    assert_eq!(bus.len(), 2);
+}
+
+#[test]
+fn test_bus_rollback_failure() {
+    let mut axes = Vec::new();
+
+    for axis in 0..2u16 {
+        let (node_thread, main_thread) = create_mainnodethread();
+        thread::spawn(move || {
+            let mut node_cfg = init_node(node_thread);
+            if axis == 1 {
+                let _ = node_cfg.read();
+                let _ = node_cfg.reject_state_change(0x1u32);
+                return;
+            }
+
+            let mut node_cyc = accept_into_cyclic(node_cfg);
+            let _ = node_cyc.read();
+            let _ = node_cyc.reject_state_change(0x2u32);
+        });
+        axes.push(create_main_mcb(
+            Some(main_thread),
+            ExtMode::Extended,
+            NODE_SUBNODE,
+        ));
+    }
+
+    let Err(error) = create_bus(axes).init().into_cyclic() else {
+        panic!("Something wrong");
+    };
+
+    // The first axis could not go back, so it is reported in cyclic state
+    assert!(matches!(error.errors[0], (1, IntfError::Access(0x1u32))));
+    assert!(matches!(error.errors[1], (0, IntfError::Access(0x2u32))));
+    assert!(matches!(error.axes[0], BusAxis::Cyclic(_)));
+    assert!(matches!(error.axes[1], BusAxis::Config(_)));
+    assert!(error.into_config_bus().is_err());
+}
+
+#[test]
+fn test_state_change_rejected_in_cyclic() {
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut node_cyc = accept_into_cyclic(init_node(node_thread));
+        while let Ok(request) = node_cyc.read() {
+            match request.command {
+                CommandType::StateChange(TargetState::Config) => {
+                    let _ = node_cyc.reject_state_change(0x2u32);
+                }
+                _ => {
+                    let data = node_cyc.get_cyclic_data(&request, 2).to_vec();
+                    let _ = node_cyc.write_cyclic(&data);
+                }
+            }
+        }
+    });
+
+    let mcb_main_cfg = init_state_change_main(main_thread);
+    let Ok(mcb_main_cyc) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+    let Err((mut mcb_main_cyc, error)) = mcb_main_cyc.into_config() else {
+        panic!("Something wrong");
+    };
+
+    assert!(matches!(error, IntfError::Access(0x2u32)));
+    assert_eq!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102, 0x0304]).unwrap(),
+        [0x0102, 0x0304]
+    );
+}
+
+#[test]
+fn test_frame_decode() {
+    let frame = Frame::parse(&[0x0000, 0x00A6, 0x0001, 0x0000, 0x0000, 0x0000, 0xE955]);
+
+    assert_eq!(frame.subnode(), 0);
+    assert_eq!(frame.command(), FrameCommand::Ack);
+    assert_eq!(frame.address(), 0x00A);
+    assert!(!frame.is_extended());
+    assert!(!frame.is_error());
+    assert_eq!(frame.config_data(), [0x0001, 0x0000, 0x0000, 0x0000]);
+    assert_eq!(frame.crc(), Some(0xE955));
+    assert!(frame.validate().is_empty());
+    assert_eq!(
+        frame.to_string(),
+        "subnode 0 | Ack | address 0x00A | config [0001, 0000, 0000, 0000] | crc 0xE955"
+    );
+
+    let frame = Frame::parse(&[0x0001, 0x0008, 0x0002, 0x0601, 0x0000, 0x0000, 0x0000]);
+    assert!(frame.is_error());
+    assert_eq!(frame.error_code(), Some(0x0601_0002));
+
+    let frame = Frame::parse(&[0x0001, 0x000A, 0x0002, 0x0000, 0x0000, 0x0000, 0x0000]);
+    assert_eq!(frame.target_state(), Some(TargetState::Cyclic));
+}
+
+#[test]
+fn test_frame_decode_payloads() {
+    let mut words = vec![0x0001, 0x00A7, 0x0005, 0x0000, 0x0000, 0x0000, 0x0000];
+    words.extend([0x6568, 0x6C6C, 0x006F]);
+    let crc = match Frame::parse(&words).validate()[..] {
+        [FrameProblem::Crc { computed, .. }] => computed,
+        _ => panic!("Something wrong"),
+    };
+    words[6] = crc;
+
+    let frame = Frame::parse(&words);
+    assert_eq!(frame.command(), FrameCommand::Ack);
+    assert!(frame.is_extended());
+    assert_eq!(frame.extended_data(), [0x6568, 0x6C6C, 0x006F]);
+    assert!(frame.cyclic_data().is_empty());
+    assert!(frame.validate().is_empty());
+
+    let frame = Frame::parse(&[0x0001, 0x000E, 0, 0, 0, 0, 0, 0x1234, 0x5678]);
+    assert_eq!(frame.command(), FrameCommand::Idle);
+    assert_eq!(frame.cyclic_data(), [0x1234, 0x5678]);
+    assert!(frame.extended_data().is_empty());
+}
+
+#[test]
+fn test_frame_validation_problems() {
+    assert_eq!(
+        Frame::parse(&[0x0001, 0x00A2]).validate(),
+        [FrameProblem::TooShort(2)]
+    );
+
+    let frame = Frame::parse(&[0x0000, 0x00A6, 0x0001, 0x0000, 0x0000, 0x0000, 0x0000]);
+    assert_eq!(
+        frame.validate(),
+        [FrameProblem::Crc {
+            received: 0x0000,
+            computed: 0xE955
+        }]
+    );
+    assert!(frame.validate_with(|_| 0x0000).is_empty());
+
+    let problems = Frame::parse(&[0x0000, 0x00A0, 0, 0, 0, 0, 0]).validate_with(|_| 0x0000);
+    assert_eq!(problems, [FrameProblem::UnknownCommand(0x0)]);
+
+    let problems = Frame::parse(&[0x0000, 0x000F, 0, 0, 0, 0, 0]).validate_with(|_| 0x0000);
+    assert_eq!(problems, [FrameProblem::UnexpectedExtBit]);
+
+    let problems =
+        Frame::parse(&[0x0000, 0x00A5, 0x0008, 0, 0, 0, 0, 0x0001]).validate_with(|_| 0x0000);
+    assert_eq!(
+        problems,
+        [FrameProblem::ExtendedSize {
+            declared: 8,
+            available: 2
+        }]
+    );
+}
+
+fn record_session() -> Vec<u8> {
+    const ADDRESS: u16 = 10u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        serve_requests(init_node(node_thread), |node_cfg, request| {
+            match request.command {
+                CommandType::Read => {
+                    let _ = node_cfg.write_u16(request.address, 0x1234u16);
+                }
+                _ => {
+                    let data = node_cfg.get_data_u16(&request);
+                    let _ = node_cfg.write_u16(request.address, data);
+                }
+            }
+        });
+    });
+
+    let mut log = Vec::new();
+    let recorder = Recorder::new(main_thread, &mut log).unwrap();
+    let mut mcb_main_cfg = create_main_mcb(Some(recorder), ExtMode::Extended, MAIN_SUBNODE).init();
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Ok(0x1234u16)
+    ));
+    assert!(mcb_main_cfg
+        .write_u16(NODE_SUBNODE, ADDRESS, 0x5678u16)
+        .is_ok());
+
+    drop(mcb_main_cfg);
+    log
+}
+
+#[test]
+fn test_capture_log() {
+    let capture = Capture::read_log(&record_session()[..]).unwrap();
+
+    let directions: Vec<Direction> = capture.records.iter().map(|r| r.direction).collect();
+    assert_eq!(
+        directions,
+        [
+            Direction::Write,
+            Direction::Read,
+            Direction::Write,
+            Direction::Read
+        ]
+    );
+
+    let request = Frame::parse(&capture.records[2].words);
+    assert_eq!(request.command(), FrameCommand::Write);
+    assert_eq!(request.config_data()[0], 0x5678u16);
+    assert!(capture
+        .records
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+    assert!(Capture::read_log(&b"NOTALOG0"[..]).is_err());
+}
+
+#[test]
+fn test_capture_replay() {
+    let capture = Capture::read_log(&record_session()[..]).unwrap();
+
+    let mut mcb_main_cfg = create_main_mcb(
+        Some(Replay::new(capture.clone())),
+        ExtMode::Extended,
+        MAIN_SUBNODE,
+    )
+    .init();
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, 10u16),
+        Ok(0x1234u16)
+    ));
+    assert!(mcb_main_cfg
+        .write_u16(NODE_SUBNODE, 10u16, 0x5678u16)
+        .is_ok());
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, 10u16),
+        Err(IntfError::Interface)
+    ));
+
+    let mut mcb_main_cfg =
+        create_main_mcb(Some(Replay::new(capture)), ExtMode::Extended, MAIN_SUBNODE).init();
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, 11u16),
+        Err(IntfError::Interface)
+    ));
+}
+
+#[test]
+fn test_capture_pcapng_export() {
+    let capture = Capture::read_log(&record_session()[..]).unwrap();
+    let mut pcapng = Vec::new();
+    capture.write_pcapng(&mut pcapng).unwrap();
+
+    let word = |offset: usize| u32::from_le_bytes(pcapng[offset..offset + 4].try_into().unwrap());
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    while offset < pcapng.len() {
+        let length = word(offset + 4) as usize;
+        assert_eq!(word(offset + length - 4) as usize, length);
+        blocks.push((word(offset), offset));
+        offset += length;
+    }
+
+    assert_eq!(offset, pcapng.len());
+    assert_eq!(blocks.len(), 2 + capture.records.len());
+    assert_eq!(blocks[0].0, 0x0A0D_0D0A);
+    assert_eq!(blocks[1].0, 0x0000_0001);
+    assert_eq!(
+        u16::from_le_bytes([pcapng[blocks[1].1 + 8], pcapng[blocks[1].1 + 9]]),
+        PCAPNG_LINKTYPE
+    );
+    assert!(blocks[2..].iter().all(|(kind, _)| *kind == 0x0000_0006));
+    assert_eq!(
+        word(blocks[2].1 + 20) as usize,
+        capture.records[0].words.len() * 2
+    );
+}
+
+#[test]
+fn test_gateway_forwarding() {
+    const DRIVE_SUBNODE: u8 = 3u8;
+    const STR_ADDRESS: u16 = 0x020u16;
+    const ERR_ADDRESS: u16 = 11u16;
+    const NAME: &str = "gateway between transports";
+    let (gw_node_thread, pc_main_thread) = create_mainnodethread();
+    let (drive_node_thread, gw_main_thread) = create_mainnodethread();
+    let (written_tx, written_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let node_cfg = init_subnode_node(drive_node_thread, ExtMode::Extended, DRIVE_SUBNODE);
+        serve_requests(node_cfg, |node_cfg, request| {
+            let _ = match request.command {
+                CommandType::Read if request.address == STR_ADDRESS => {
+                    node_cfg.write_str(request.address, NAME)
+                }
+                CommandType::Read if request.address == ERR_ADDRESS => {
+                    node_cfg.error(0, 0x0601_0000u32)
+                }
+                CommandType::Read => node_cfg.write_u16(request.address, 0x1234u16),
+                _ => {
+                    let words = node_cfg.get_data_words(&request).to_vec();
+                    written_tx.send((request.address, words)).unwrap();
+                    node_cfg.ack(request.address)
+                }
+            };
+        });
+    });
+
+    thread::spawn(move || {
+        let mut gateway = create_gateway(
+            init_subnode_node(gw_node_thread, ExtMode::Segmented, NODE_SUBNODE),
+            create_main_mcb(Some(gw_main_thread), ExtMode::Extended, MAIN_SUBNODE).init(),
+        );
+        gateway.route(NODE_SUBNODE, DRIVE_SUBNODE);
+        gateway.run()
+    });
+
+    let mut mcb_main_cfg =
+        create_main_mcb(Some(pc_main_thread), ExtMode::Segmented, MAIN_SUBNODE).init();
+
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, 10u16),
+        Ok(0x1234u16)
+    ));
+    assert_eq!(
+        mcb_main_cfg.read_str(NODE_SUBNODE, STR_ADDRESS).unwrap(),
+        NAME
+    );
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ERR_ADDRESS),
+        Err(IntfError::Access(0x0601_0000u32))
+    ));
+
+    assert!(mcb_main_cfg
+        .write_u32(NODE_SUBNODE, 12u16, 0xDEAD_BEEFu32)
+        .is_ok());
+    let (address, words) = written_rx.recv().unwrap();
+    assert_eq!(address, 12u16);
+    assert_eq!(words[..2], [0xBEEFu16, 0xDEADu16]);
+
+    let payload: Vec<u16> = (0..10u16).collect();
+    assert!(mcb_main_cfg
+        .write_words(NODE_SUBNODE, 13u16, &payload)
+        .is_ok());
+    let (address, words) = written_rx.recv().unwrap();
+    assert_eq!(address, 13u16);
+    assert_eq!(words[..payload.len()], payload[..]);
+}
+
+#[test]
+fn test_gateway_unreachable_drive() {
+    let (gw_node_thread, pc_main_thread) = create_mainnodethread();
+    let (drive_node_thread, gw_main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        while drive_node_thread.rx_channel.recv().is_ok() {
+            let mut corrupted = [0u16; MAX_FRAME_SIZE];
+            corrupted[6] = 0xFFFFu16;
+            drive_node_thread.tx_channel.send(corrupted).unwrap();
+        }
+    });
+
+    thread::spawn(move || {
+        let mut gateway = create_gateway(
+            init_node(gw_node_thread),
+            create_main_mcb(Some(gw_main_thread), ExtMode::Extended, MAIN_SUBNODE).init(),
+        );
+        gateway.run()
+    });
+
+    let mut mcb_main_cfg = init_state_change_main(pc_main_thread);
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, 10u16),
+        Err(IntfError::Access(ERR_DRIVE_UNREACHABLE))
+    ));
+
+    let Err((_, error)) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+    assert!(matches!(error, IntfError::Access(ERR_STATE_CHANGE)));
+}
+
+#[test]
+fn test_node_multiple_subnodes() {
+    const COMM_SUBNODE: u8 = 0u8;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut node_cfg = init_node(node_thread);
+        node_cfg.add_subnode(COMM_SUBNODE);
+        node_cfg.add_subnode(COMM_SUBNODE);
+        assert_eq!(node_cfg.subnodes(), [NODE_SUBNODE, COMM_SUBNODE]);
+
+        serve_requests(node_cfg, |node_cfg, request| {
+            let _ = node_cfg.write_u8(request.address, request.subnode);
+        });
+    });
+
+    let mut mcb_main_cfg = init_state_change_main(main_thread);
+    assert!(matches!(
+        mcb_main_cfg.read_u8(COMM_SUBNODE, 10u16),
+        Ok(COMM_SUBNODE)
+    ));
+    assert!(matches!(
+        mcb_main_cfg.read_u8(NODE_SUBNODE, 10u16),
+        Ok(NODE_SUBNODE)
+    ));
+}
+
+#[test]
+fn test_node_dispatcher() {
+    const COMM_SUBNODE: u8 = 0u8;
+    const AXIS_SUBNODE: u8 = 1u8;
+    const ADDRESS: u16 = 0x011u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut comm_registers = HashMap::from([(ADDRESS, 0x00C0u16)]);
+        let mut axis_registers = HashMap::from([(ADDRESS, 0x0A01u16)]);
+        let table = |registers: &mut HashMap<u16, u16>,
+                     node_cfg: &mut Node<Config, NodeThread<[u16; MAX_FRAME_SIZE]>>,
+                     request: Request| match request.command {
+            CommandType::Read => match registers.get(&request.address) {
+                Some(value) => node_cfg.write_u16(request.address, *value),
+                None => node_cfg.error(0, 0x0601_0000u32),
+            },
+            _ => {
+                registers.insert(request.address, node_cfg.get_data_u16(&request));
+                node_cfg.ack(request.address)
+            }
+        };
+
+        let mut dispatcher = Dispatcher::new(init_node(node_thread));
+        dispatcher.handle(COMM_SUBNODE, |node_cfg, request| {
+            table(&mut comm_registers, node_cfg, request)
+        });
+        dispatcher.handle(AXIS_SUBNODE, |node_cfg, request| {
+            table(&mut axis_registers, node_cfg, request)
+        });
+        dispatcher.run()
+    });
+
+    let mut mcb_main_cfg = init_state_change_main(main_thread);
+    assert!(matches!(
+        mcb_main_cfg.read_u16(COMM_SUBNODE, ADDRESS),
+        Ok(0x00C0u16)
+    ));
+    assert!(matches!(
+        mcb_main_cfg.read_u16(AXIS_SUBNODE, ADDRESS),
+        Ok(0x0A01u16)
+    ));
+
+    assert!(mcb_main_cfg
+        .write_u16(AXIS_SUBNODE, ADDRESS, 0x0A02u16)
+        .is_ok());
+    assert!(matches!(
+        mcb_main_cfg.read_u16(AXIS_SUBNODE, ADDRESS),
+        Ok(0x0A02u16)
+    ));
+    assert!(matches!(
+        mcb_main_cfg.read_u16(COMM_SUBNODE, ADDRESS),
+        Ok(0x00C0u16)
+    ));
+
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Err(IntfError::Access(ERR_NO_HANDLER))
+    ));
+}
+
+#[test]
+fn test_axis_access() {
+    const COMM_SUBNODE: u8 = 0u8;
+    const ADDRESS: u16 = 0x020u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut node_cfg = init_node(node_thread);
+        node_cfg.add_subnode(COMM_SUBNODE);
+        let mut registers: HashMap<(u8, u16), u32> = HashMap::new();
+
+        serve_requests(node_cfg, |node_cfg, request| {
+            let _ = match request.command {
+                CommandType::Read if request.subnode == COMM_SUBNODE => {
+                    node_cfg.write_str(request.address, "communications")
+                }
+                CommandType::Read => {
+                    let value = registers.get(&(request.subnode, request.address));
+                    node_cfg.write_u32(request.address, *value.unwrap_or(&0u32))
+                }
+                _ => {
+                    let value = node_cfg.get_data_u32(&request);
+                    registers.insert((request.subnode, request.address), value);
+                    node_cfg.write_u32(request.address, value)
+                }
+            };
+        });
+    });
+
+    let mut mcb_main_cfg = init_state_change_main(main_thread);
+    assert_eq!(mcb_main_cfg.subnode(), NODE_SUBNODE);
+
+    let mut axis = mcb_main_cfg.default_axis();
+    assert_eq!(axis.subnode(), NODE_SUBNODE);
+    assert!(axis.write_u32(ADDRESS, 0x0102_0304u32).is_ok());
+    assert!(matches!(axis.read_u32(ADDRESS), Ok(0x0102_0304u32)));
+    assert!(matches!(axis.read_i32(ADDRESS), Ok(0x0102_0304i32)));
+
+    let mut comm = mcb_main_cfg.axis(COMM_SUBNODE);
+    assert_eq!(comm.read_str(ADDRESS).unwrap(), "communications");
+
+    assert!(matches!(
+        mcb_main_cfg.read_u32(NODE_SUBNODE, ADDRESS),
+        Ok(0x0102_0304u32)
+    ));
+}
+
+fn parameter_registers() -> Vec<RegisterDescription> {
+    vec![
+        RegisterDescription::new(
+            "max_current",
+            NODE_SUBNODE,
+            0x021,
+            DataType::U16,
+            Access::ReadWrite,
+        )
+        .after("motor_type"),
+        RegisterDescription::new(
+            "motor_type",
+            NODE_SUBNODE,
+            0x020,
+            DataType::U8,
+            Access::ReadWrite,
+        ),
+        RegisterDescription::new(
+            "position",
+            NODE_SUBNODE,
+            0x030,
+            DataType::I32,
+            Access::ReadOnly,
+        ),
+        RegisterDescription::new(
+            "name",
+            NODE_SUBNODE,
+            0x040,
+            DataType::Str,
+            Access::ReadWrite,
+        ),
+        RegisterDescription::new(
+            "offset",
+            NODE_SUBNODE,
+            0x041,
+            DataType::I64,
+            Access::ReadWrite,
+        ),
+    ]
+}
+
+#[test]
+fn test_parameters_save_restore() {
+    const MAX_CURRENT: u16 = 0x021u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (write_tx, write_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut registers: HashMap<u16, Vec<u16>> = HashMap::new();
+        registers.insert(0x020, vec![3]);
+        registers.insert(MAX_CURRENT, vec![50]);
+        registers.insert(0x030, vec![0x1234, 0]);
+        registers.insert(0x040, vec![0x6F64, 0x0061]);
+        registers.insert(0x041, vec![0xFFFE, 0xFFFF, 0xFFFF, 0xFFFF]);
+
+        serve_requests(init_node(node_thread), |node_cfg, request| {
+            let _ = match request.command {
+                CommandType::Read | CommandType::ExtRead => {
+                    node_cfg.write_words(request.address, &registers[&request.address])
+                }
+                _ => {
+                    let mut words = node_cfg.get_data_words(&request).to_vec();
+                    // The drive limits the current to 100
+                    if request.address == MAX_CURRENT {
+                        words[0] = words[0].min(100);
+                    }
+                    let _ = write_tx.send(request.address);
+                    registers.insert(request.address, words);
+                    node_cfg.ack(request.address)
+                }
+            };
+        });
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    let registers = parameter_registers();
+
+    let saved = mcb_main_cfg.save_parameters(&registers).unwrap();
+    let names: Vec<&str> = saved.parameters.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["max_current", "motor_type", "name", "offset"]);
+    assert_eq!(saved.get("motor_type").unwrap().value, Value::U8(3));
+    assert_eq!(saved.get("max_current").unwrap().value, Value::U16(50));
+    assert_eq!(
+        saved.get("name").unwrap().value,
+        Value::Str("doa".to_string())
+    );
+    assert_eq!(saved.get("offset").unwrap().value, Value::I64(-2));
+
+    let mut set = saved.clone();
+    set.parameters[0].value = Value::U16(150);
+    set.parameters.push(Parameter {
+        name: "unknown".to_string(),
+        subnode: NODE_SUBNODE,
+        address: 0x050,
+        value: Value::U32(1),
+    });
+
+    let report = mcb_main_cfg.restore_parameters(&registers, &set).unwrap();
+    assert!(!report.is_clean());
+    assert_eq!(
+        report.written,
+        ["motor_type", "max_current", "name", "offset"]
+    );
+    assert_eq!(report.skipped, ["unknown"]);
+    assert!(report.failures.is_empty());
+    assert_eq!(report.differences.len(), 1);
+    assert_eq!(report.differences[0].name, "max_current");
+    assert_eq!(report.differences[0].expected, Value::U16(150));
+    assert_eq!(report.differences[0].actual, Value::U16(100));
+
+    let order: Vec<u16> = write_rx.try_iter().collect();
+    assert_eq!(order, [0x020, MAX_CURRENT, 0x040, 0x041]);
+
+    let report = mcb_main_cfg.restore_parameters(&registers, &saved).unwrap();
+    assert!(report.is_clean());
+}
+
+#[test]
+fn test_parameters_invalid_dependencies() {
+    let (_node_thread, main_thread) = create_mainnodethread();
+    let mut mcb_main_cfg = init_main(main_thread);
+    let set = ParameterSet::default();
+
+    let mut registers = parameter_registers();
+    registers[1] = registers[1].clone().after("max_current");
+    let error = mcb_main_cfg
+        .restore_parameters(&registers, &set)
+        .unwrap_err();
+    assert!(matches!(error.error, IntfError::InvalidParameter));
+
+    let registers = vec![RegisterDescription::new(
+        "gain",
+        NODE_SUBNODE,
+        0x020,
+        DataType::F32,
+        Access::ReadWrite,
+    )
+    .after("missing")];
+    let error = mcb_main_cfg
+        .restore_parameters(&registers, &set)
+        .unwrap_err();
+    assert_eq!(error.name, "missing");
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+fn file_format_parameters() -> ParameterSet {
+    ParameterSet {
+        parameters: vec![
+            Parameter {
+                name: "gain".to_string(),
+                subnode: 1,
+                address: 0x010,
+                value: Value::F32(0.1),
+            },
+            Parameter {
+                name: "serial".to_string(),
+                subnode: 0,
+                address: 0x6E6,
+                value: Value::U64(u64::MAX),
+            },
+            Parameter {
+                name: "name".to_string(),
+                subnode: 0,
+                address: 0x6E0,
+                value: Value::Str("axis x".to_string()),
+            },
+            Parameter {
+                name: "limit".to_string(),
+                subnode: 1,
+                address: 0x011,
+                value: Value::F64(f64::INFINITY),
+            },
+            Parameter {
+                name: "offset".to_string(),
+                subnode: 1,
+                address: 0x012,
+                value: Value::I16(-7),
+            },
+        ],
+    }
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_parameters_toml() {
+    let set = file_format_parameters();
+
+    let toml = set.to_toml().unwrap();
+    assert!(toml.contains("value = 0.1\n"));
+    assert_eq!(ParameterSet::from_toml(&toml).unwrap(), set);
+
+    let wrong =
+        "[[parameter]]\nname = \"a\"\nsubnode = 1\naddress = 1\ndtype = \"u8\"\nvalue = 300\n";
+    assert!(ParameterSet::from_toml(wrong).is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_parameters_json() {
+    let set = file_format_parameters();
+
+    let json = set.to_json().unwrap();
+    assert!(json.contains("\"value\": \"18446744073709551615\""));
+    assert_eq!(ParameterSet::from_json(&json).unwrap(), set);
+}
+
+/// Software model of an STM32 CRC unit set to 16 bit polynomial 0x1021
+#[derive(Default)]
+struct CrcUnitModel {
+    value: u16,
+    fed: usize,
+}
+
+impl CrcUnit for CrcUnitModel {
+    fn reset(&mut self) {
+        self.value = 0;
+    }
+
+    fn feed(&mut self, half_word: u16) {
+        self.value ^= half_word;
+        for _ in 0..16 {
+            self.value = match self.value & 0x8000 {
+                0 => self.value << 1,
+                _ => (self.value << 1) ^ 0x1021,
+            };
+        }
+        self.fed += 1;
+    }
+
+    fn result(&mut self) -> u16 {
+        self.value
+    }
+}
+
+fn check_crc_vectors<E: CrcEngine>(engine: &mut E) {
+    // (frame words, little endian CRC, big endian CRC)
+    let vectors: [([u16; 6], u16, u16); 4] = [
+        ([0x0000, 0x00A6, 0x0001, 0, 0, 0], 0xE955, 0xFAA7),
+        ([0x0001, 0x00A6, 0x0001, 0, 0, 0], 0xEA20, 0x22EE),
+        ([0x0002, 0x0114, 0x1234, 0, 0, 0], 0xCA43, 0x2ABE),
+        ([0; 6], 0x0000, 0x0000),
+    ];
+
+    for (words, little, big) in vectors {
+        assert_eq!(engine.checksum(&words, ByteOrder::Little), little);
+        assert_eq!(engine.checksum(&words, ByteOrder::Big), big);
+
+        let mut frame = [0xFFFFu16; MAX_FRAME_SIZE];
+        frame[..6].copy_from_slice(&words);
+        assert_eq!(engine.frame_crc(&frame), little);
+    }
+
+    // CRC-16/XMODEM check value of "12345678"
+    let text = [0x3231u16, 0x3433u16, 0x3635u16, 0x3837u16];
+    assert_eq!(engine.checksum(&text, ByteOrder::Little), 0x9015);
+    let text = text.map(u16::swap_bytes);
+    assert_eq!(engine.checksum(&text, ByteOrder::Big), 0x9015);
+    assert_eq!(engine.checksum(&[], ByteOrder::Little), 0x0000);
+}
+
+#[test]
+fn test_crc_engines() {
+    check_crc_vectors(&mut TableCrc);
+    check_crc_vectors(&mut BitwiseCrc);
+
+    let mut hardware = HardwareCrc::new(CrcUnitModel::default());
+    check_crc_vectors(&mut hardware);
+    assert_eq!(hardware.into_inner().fed, 80);
+}
+
+#[test]
+fn test_crc_engine_frame_validation() {
+    let mut hardware = HardwareCrc::new(CrcUnitModel::default());
+    let frame = Frame::parse(&[0x0002, 0x0114, 0x1234, 0, 0, 0, 0xCA43]);
+    assert!(frame.validate().is_empty());
+    assert!(frame
+        .validate_with(|words| hardware.frame_crc(words))
+        .is_empty());
+    assert!(frame
+        .validate_with(|words| BitwiseCrc.checksum(words, ByteOrder::Big))
+        .iter()
+        .any(|problem| matches!(
+            problem,
+            FrameProblem::Crc {
+                computed: 0x2ABE,
+                ..
+            }
+        )));
+}
+
+#[test]
+fn test_payload_crc() {
+    const ADDRESS: u16 = 0x030u16;
+    const DATA: &str = "payload protected";
+    let words: Vec<u16> = (1..=10).collect();
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (request_tx, request_rx) = mpsc::channel();
+
+    let node_words = words.clone();
+    thread::spawn(move || {
+        let mut node_cfg = init_node(node_thread);
+        node_cfg.set_payload_crc(true);
+
+        let request = node_cfg.read().unwrap();
+        request_tx
+            .send(node_cfg.get_data_words(&request).to_vec())
+            .unwrap();
+        let _ = node_cfg.ack(request.address);
+
+        let request = node_cfg.read().unwrap();
+        let _ = node_cfg.write_words(request.address, &node_words);
+
+        let request = node_cfg.read().unwrap();
+        let _ = node_cfg.write_str(request.address, DATA);
+
+        // Frames without payload CRC are rejected
+        request_tx
+            .send(match node_cfg.read() {
+                Err(IntfError::PayloadCrc) => vec![],
+                _ => vec![0xFFFF],
+            })
+            .unwrap();
+
+        // Answers without payload CRC too
+        node_cfg.set_payload_crc(false);
+        let request = node_cfg.read().unwrap();
+        let _ = node_cfg.write_words(request.address, &node_words);
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    mcb_main_cfg.set_payload_crc(true);
+    assert!(mcb_main_cfg.has_payload_crc());
+
+    assert!(mcb_main_cfg
+        .write_words(NODE_SUBNODE, ADDRESS, &words)
+        .is_ok());
+    assert_eq!(request_rx.recv().unwrap(), words);
+    assert_eq!(
+        mcb_main_cfg.read_words(NODE_SUBNODE, ADDRESS).unwrap(),
+        words
+    );
+    assert_eq!(mcb_main_cfg.read_str(NODE_SUBNODE, ADDRESS).unwrap(), DATA);
+
+    mcb_main_cfg.set_payload_crc(false);
+    let _ = mcb_main_cfg.write_str(NODE_SUBNODE, ADDRESS, DATA);
+    assert!(request_rx.recv().unwrap().is_empty());
+
+    mcb_main_cfg.set_payload_crc(true);
+    assert!(matches!(
+        mcb_main_cfg.read_words(NODE_SUBNODE, ADDRESS),
+        Err(IntfError::PayloadCrc)
+    ));
+}
+
+#[test]
+fn test_codec_byte_orders() {
+    // "drive" as stored in the config words of a frame
+    let words = [0x7264u16, 0x7669u16, 0x0065u16, 0x0000u16];
+    let wire = [0x64u8, 0x72, 0x69, 0x76, 0x65, 0x00, 0x00, 0x00];
+
+    assert_eq!(
+        ByteOrder::native().to_bytes(0x0102),
+        0x0102u16.to_ne_bytes()
+    );
+    assert_eq!(words_to_bytes(&words, ByteOrder::Little), wire);
+    assert_eq!(bytes_to_words(&wire, ByteOrder::Little), words);
+    assert_eq!(bytes_to_words(&wire[..5], ByteOrder::Little), words[..3]);
+
+    // Reinterpreting the memory of a big endian host swaps the characters
+    let memory = words_to_bytes(&words, ByteOrder::Big);
+    assert_ne!(memory, wire);
+    assert_eq!(memory[..2], [0x72, 0x64]);
+
+    assert_eq!(decode_str(&[0x00E9, 0x0041]), "\u{e9}");
+    assert_eq!(decode_str(&[]), "");
+}
+
+/// Link carrying the frames as bytes, each word split in the given order.
+/// Every frame sent by either side is copied to `wire`
+struct ByteLink {
+    order: ByteOrder,
+    tx_channel: Sender<Vec<u8>>,
+    rx_channel: Receiver<Vec<u8>>,
+    wire: Sender<Vec<u8>>,
+}
+
+fn create_byte_link(order: ByteOrder) -> (ByteLink, ByteLink, Receiver<Vec<u8>>) {
+    let (mtx, mrx) = mpsc::channel();
+    let (stx, srx) = mpsc::channel();
+    let (wire, wire_rx) = mpsc::channel();
+
+    (
+        ByteLink {
+            order,
+            tx_channel: stx,
+            rx_channel: mrx,
+            wire: wire.clone(),
+        },
+        ByteLink {
+            order,
+            tx_channel: mtx,
+            rx_channel: srx,
+            wire,
+        },
+        wire_rx,
+    )
+}
+
+impl PhysicalInterface for ByteLink {
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        let bytes = words_to_bytes(frame, self.order);
+        self.wire.send(bytes.clone()).unwrap();
+        self.tx_channel
+            .send(bytes)
+            .map_err(|_| IntfError::Interface)?;
+        Ok(Success)
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        let bytes = self.rx_channel.recv().map_err(|_| IntfError::Interface)?;
+        Ok(Data(bytes_to_words(&bytes, self.order).into_boxed_slice()))
+    }
+}
+
+#[test]
+fn test_big_endian_link() {
+    const ADDRESS: u16 = 0x040u16;
+    const DATA: &str = "drive";
+    let (node_link, main_link, wire) = create_byte_link(ByteOrder::Big);
+
+    thread::spawn(move || {
+        let mcb_node: Node<Init, ByteLink> =
+            create_node_mcb(Some(node_link), ExtMode::Extended, NODE_SUBNODE);
+        let mut node_cfg = mcb_node.init();
+
+        let request = node_cfg.read().unwrap();
+        assert_eq!(node_cfg.get_data_str(&request), DATA);
+        let _ = node_cfg.ack(request.address);
+
+        let request = node_cfg.read().unwrap();
+        let _ = node_cfg.write_str(request.address, DATA);
+    });
+
+    let mcb_main: Main<Init, ByteLink> =
+        create_main_mcb(Some(main_link), ExtMode::Extended, MAIN_SUBNODE);
+    let mut mcb_main_cfg = mcb_main.init();
+
+    let result = mcb_main_cfg.write_str(NODE_SUBNODE, ADDRESS, DATA);
+    assert!(matches!(result, Ok(IntfResult::Success)));
+    assert_eq!(mcb_main_cfg.read_str(NODE_SUBNODE, ADDRESS).unwrap(), DATA);
+
+    // Write request: header, command, "drive" and the CRC of the little
+    // endian words, each word most significant byte first
+    let request = wire.recv().unwrap();
+    assert_eq!(
+        request[..14],
+        [0x00, 0x02, 0x04, 0x04, 0x72, 0x64, 0x76, 0x69, 0x00, 0x65, 0x00, 0x00, 0x73, 0x09]
+    );
+
+    // Ack, then the read request and the answer carrying "drive"
+    let _ack = wire.recv().unwrap();
+    let _read = wire.recv().unwrap();
+    let answer = wire.recv().unwrap();
+    assert_eq!(answer[4..10], [0x72, 0x64, 0x76, 0x69, 0x00, 0x65]);
+}
+
+#[test]
+fn test_write_str_content() {
+    const ADDRESS: u16 = 0x040u16;
+    const DATA: &str = "odd sized name";
+    let (extended_node, extended_main) = create_mainnodethread();
+    let (segmented_node, segmented_main) = create_mainnodethread();
+    let (content_tx, content_rx) = mpsc::channel();
+
+    for (node_thread, mode) in [
+        (extended_node, ExtMode::Extended),
+        (segmented_node, ExtMode::Segmented),
+    ] {
+        let content_tx = content_tx.clone();
+        thread::spawn(move || {
+            let mut node_cfg = init_subnode_node(node_thread, mode, NODE_SUBNODE);
+            let request = node_cfg.read().unwrap();
+            content_tx.send(node_cfg.get_data_str(&request)).unwrap();
+            let _ = node_cfg.ack(request.address);
+        });
+    }
+
+    drop(content_tx);
+
+    let mut mcb_main_cfg = init_main(extended_main);
+    let _ = mcb_main_cfg.write_str(NODE_SUBNODE, ADDRESS, &DATA[1..]);
+    assert_eq!(content_rx.recv().unwrap(), &DATA[1..]);
+
+    let mut mcb_main_cfg = init_segmented_main(segmented_main);
+    let _ = mcb_main_cfg.write_str(NODE_SUBNODE, ADDRESS, DATA);
+    assert_eq!(content_rx.recv().unwrap(), DATA);
+}
+
+/// Frame with the given config words and a valid CRC
+fn sealed_frame(words: [u16; 6]) -> [u16; MAX_FRAME_SIZE] {
+    let mut frame = [0u16; MAX_FRAME_SIZE];
+    frame[..6].copy_from_slice(&words);
+    frame[6] = TableCrc.frame_crc(&words);
+    frame
+}
+
+#[test]
+fn test_main_statistics() {
+    const ADDRESS: u16 = 0x011u16;
+    const ERROR_CODE: u32 = 0x0601_0000u32;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let ack = (ADDRESS << 4) | 0x6;
+        let mut corrupted = [0u16; MAX_FRAME_SIZE];
+        corrupted[6] = 0xFFFF;
+        let answers = [
+            corrupted,
+            sealed_frame([NODE_SUBNODE as u16, ack, 0x1234, 0, 0, 0]),
+            sealed_frame([NODE_SUBNODE as u16, 0x0008, 0x0000, 0x0601, 0, 0]),
+            sealed_frame([5, ack, 0x1234, 0, 0, 0]),
+        ];
+        for answer in answers {
+            if node_thread.rx_channel.recv().is_err() {
+                return;
+            }
+            node_thread.tx_channel.send(answer).unwrap();
+        }
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    mcb_main_cfg.set_retries(1);
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Ok(0x1234u16)
+    ));
+
+    let stats = mcb_main_cfg.statistics();
+    assert_eq!(stats.frames_sent, 2);
+    assert_eq!(stats.frames_received, 2);
+    assert_eq!(stats.crc_errors, 1);
+    assert_eq!(stats.retries, 1);
+    assert_eq!(stats.latency.count, 1);
+    assert_eq!(stats.latency.buckets.iter().sum::<u64>(), 1);
+    assert_eq!(stats.latency.buckets.len(), LATENCY_BOUNDS.len() + 1);
+    assert_eq!(stats.latency.min, stats.latency.max);
+    assert_eq!(stats.latency.mean(), stats.latency.min);
+
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Err(IntfError::Access(ERROR_CODE))
+    ));
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Err(IntfError::Access(0))
+    ));
+
+    let stats = mcb_main_cfg.statistics();
+    assert_eq!(stats.frames_sent, 4);
+    assert_eq!(stats.access_errors.get(&ERROR_CODE), Some(&1));
+    assert_eq!(stats.subnode_mismatches, 1);
+    assert_eq!(stats.wrong_commands, 0);
+    assert_eq!(stats.latency.count, 3);
+
+    mcb_main_cfg.reset_statistics();
+    assert_eq!(mcb_main_cfg.statistics(), Statistics::default());
+    assert_eq!(Statistics::default().latency.mean(), None);
+}
+
+#[test]
+fn test_main_idle_answer_statistics() {
+    const ADDRESS: u16 = 0x011u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        if node_thread.rx_channel.recv().is_ok() {
+            let idle = sealed_frame([NODE_SUBNODE as u16, 0x000E, 0x1234, 0, 0, 0]);
+            node_thread.tx_channel.send(idle).unwrap();
+        }
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    assert!(mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS).is_err());
+
+    let stats = mcb_main_cfg.statistics();
+    assert_eq!(stats.wrong_commands, 1);
+    assert!(stats.access_errors.is_empty());
+}
+
+#[test]
+fn test_main_write_not_repeated() {
+    const ADDRESS: u16 = 0x011u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut corrupted = [0u16; MAX_FRAME_SIZE];
+        corrupted[6] = 0xFFFF;
+        while node_thread.rx_channel.recv().is_ok() {
+            node_thread.tx_channel.send(corrupted).unwrap();
+        }
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    mcb_main_cfg.set_retries(2);
+    assert!(matches!(
+        mcb_main_cfg.write_u16(NODE_SUBNODE, ADDRESS, 0x1234u16),
+        Err(IntfError::Crc)
+    ));
+
+    let stats = mcb_main_cfg.statistics();
+    assert_eq!(stats.frames_sent, 1);
+    assert_eq!(stats.retries, 0);
+}
+
+#[test]
+fn test_node_statistics() {
+    const ADDRESS: u16 = 0x011u16;
+    const ERROR_CODE: u32 = 0x0601_0000u32;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    let read = (ADDRESS << 4) | 0x2;
+    main_thread
+        .tx_channel
+        .send(sealed_frame([7, read, 0, 0, 0, 0]))
+        .unwrap();
+    main_thread
+        .tx_channel
+        .send(sealed_frame([NODE_SUBNODE as u16, read, 0, 0, 0, 0]))
+        .unwrap();
+    let mut corrupted = sealed_frame([NODE_SUBNODE as u16, read, 0, 0, 0, 0]);
+    corrupted[6] ^= 1;
+    main_thread.tx_channel.send(corrupted).unwrap();
+    // A corrupted header is a CRC error, not a frame for another subnode
+    let mut corrupted = sealed_frame([NODE_SUBNODE as u16, read, 0, 0, 0, 0]);
+    corrupted[0] ^= 1;
+    main_thread.tx_channel.send(corrupted).unwrap();
+
+    let mut node_cfg = init_node(node_thread);
+    assert!(matches!(node_cfg.read(), Err(IntfError::WrongSubnode)));
+    let request = node_cfg.read().unwrap();
+    assert!(node_cfg.error(request.address, ERROR_CODE).is_ok());
+    assert!(matches!(node_cfg.read(), Err(IntfError::Crc)));
+    assert!(matches!(node_cfg.read(), Err(IntfError::Crc)));
+
+    let stats = node_cfg.statistics();
+    assert_eq!(stats.frames_received, 4);
+    assert_eq!(stats.frames_sent, 1);
+    assert_eq!(stats.subnode_mismatches, 1);
+    assert_eq!(stats.crc_errors, 2);
+    assert_eq!(stats.access_errors.get(&ERROR_CODE), Some(&1));
+    assert_eq!(stats.latency.count, 0);
+
+    node_cfg.reset_statistics();
+    assert_eq!(node_cfg.statistics(), Statistics::default());
+}
+
+#[test]
+fn test_node_watchdog() {
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (resume_tx, resume_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let Ok(mut mcb_main_cyc) = init_state_change_main(main_thread).into_cyclic() else {
+            panic!("Something wrong");
+        };
+        let _ = mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]);
+        resume_rx.recv().unwrap();
+        let _ = mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]);
+    });
+
+    let (expired_tx, expired_rx) = mpsc::channel();
+    let mut node_cfg = init_node(node_thread);
+    node_cfg.set_cycle_timeout(Some(Duration::from_millis(20)));
+    node_cfg.on_cycle_timeout(move || expired_tx.send(()).unwrap());
+    let mut node_cyc = accept_into_cyclic(node_cfg);
+
+    assert_eq!(node_cyc.watchdog(), WatchdogState::Armed);
+    let request = node_cyc.read().unwrap();
+    let _ = node_cyc.write_cyclic(node_cyc.get_cyclic_data(&request, 1).to_vec().as_slice());
+
+    thread::sleep(Duration::from_millis(30));
+    assert_eq!(node_cyc.watchdog(), WatchdogState::Expired);
+    assert_eq!(node_cyc.watchdog(), WatchdogState::Expired);
+    assert_eq!(expired_rx.try_iter().count(), 1);
+
+    resume_tx.send(()).unwrap();
+    let request = node_cyc.read().unwrap();
+    assert_eq!(node_cyc.watchdog(), WatchdogState::Armed);
+    let _ = node_cyc.write_cyclic(node_cyc.get_cyclic_data(&request, 1).to_vec().as_slice());
+
+    thread::sleep(Duration::from_millis(30));
+    assert!(matches!(node_cyc.read(), Err(IntfError::LinkLoss)));
+    assert_eq!(expired_rx.try_iter().count(), 1);
+
+    let node_cfg: Node<Config, _> = node_cyc.fall_back();
+    assert_eq!(node_cfg.subnodes(), [NODE_SUBNODE]);
+}
+
+#[test]
+fn test_main_link_loss() {
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut corrupted = [0u16; MAX_FRAME_SIZE];
+        corrupted[0] = NODE_SUBNODE as u16;
+        corrupted[6] = 0xFFFF;
+        let mut answers = vec![sealed_frame([NODE_SUBNODE as u16, 0x0006, 0, 0, 0, 0])];
+        answers.extend([corrupted; 4]);
+        answers.push(sealed_frame([NODE_SUBNODE as u16, 0x000E, 0, 0, 0, 0]));
+        for answer in answers {
+            if node_thread.rx_channel.recv().is_err() {
+                return;
+            }
+            node_thread.tx_channel.send(answer).unwrap();
+        }
+    });
+
+    let mut mcb_main_cfg = init_state_change_main(main_thread);
+    mcb_main_cfg.set_link_loss_threshold(3);
+    let Ok(mut mcb_main_cyc) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]),
+        Err(IntfError::Crc)
+    ));
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]),
+        Err(IntfError::Crc)
+    ));
+    assert!(!mcb_main_cyc.is_link_lost());
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]),
+        Err(IntfError::LinkLoss)
+    ));
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]),
+        Err(IntfError::LinkLoss)
+    ));
+    assert_eq!(mcb_main_cyc.missed_cycles(), 4);
+    assert!(mcb_main_cyc.is_link_lost());
+    assert!(mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]).is_ok());
+    assert_eq!(mcb_main_cyc.missed_cycles(), 0);
+    assert!(!mcb_main_cyc.is_link_lost());
+}
+
+/// Main interface reporting whether an answer arrived instead of blocking
+/// until it does
+struct PollingMain {
+    main_thread: MainThread<[u16; MAX_FRAME_SIZE]>,
+    answer: Option<[u16; MAX_FRAME_SIZE]>,
+}
+
+impl PhysicalInterface for PollingMain {
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        self.main_thread.raw_write(frame)
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        match self.answer.take() {
+            Some(answer) => Ok(Data(Box::new(answer))),
+            None => Err(IntfError::Interface),
+        }
+    }
+
+    fn is_data2read(&mut self) -> Result<IntfResult, IntfError> {
+        match self.main_thread.rx_channel.try_recv() {
+            Ok(answer) => {
+                self.answer = Some(answer);
+                Ok(Ready)
+            }
+            Err(mpsc::TryRecvError::Empty) => Ok(Empty),
+            Err(mpsc::TryRecvError::Disconnected) => Err(IntfError::Interface),
+        }
+    }
+}
+
+#[test]
+fn test_main_silent_node() {
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let answers = [
+            sealed_frame([NODE_SUBNODE as u16, 0x0006, 0, 0, 0, 0]),
+            sealed_frame([NODE_SUBNODE as u16, 0x000E, 0, 0, 0, 0]),
+        ];
+        for answer in answers {
+            if node_thread.rx_channel.recv().is_err() {
+                return;
+            }
+            node_thread.tx_channel.send(answer).unwrap();
+        }
+        // The node stops answering but keeps the link open
+        while node_thread.rx_channel.recv().is_ok() {}
+    });
+
+    let interface = PollingMain {
+        main_thread,
+        answer: None,
+    };
+    let mcb_main_test: Main<Init, PollingMain> =
+        create_main_mcb(Some(interface), ExtMode::Extended, NODE_SUBNODE);
+    let mut mcb_main_cfg = mcb_main_test.init();
+    mcb_main_cfg.set_link_loss_threshold(2);
+    mcb_main_cfg.set_response_timeout(Some(Duration::from_millis(10)));
+    let Ok(mut mcb_main_cyc) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+
+    assert!(mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]).is_ok());
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]),
+        Err(IntfError::Timeout)
+    ));
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]),
+        Err(IntfError::LinkLoss)
+    ));
+    assert_eq!(mcb_main_cyc.missed_cycles(), 2);
+    assert_eq!(mcb_main_cyc.statistics().timeouts, 2);
+}
+
+/// Timer driven by the test. Each sleep wakes up at the deadline plus the
+/// next of the given delays
+struct FakeTimer {
+    clock: Rc<Cell<Duration>>,
+    wake_delays: Vec<Duration>,
+    deadlines: Vec<Duration>,
+}
+
+impl Timer for FakeTimer {
+    fn now(&mut self) -> Duration {
+        self.clock.get()
+    }
+
+    fn sleep_until(&mut self, deadline: Duration) {
+        self.deadlines.push(deadline);
+        let delay = match self.wake_delays.is_empty() {
+            true => Duration::ZERO,
+            false => self.wake_delays.remove(0),
+        };
+        self.clock.set(self.clock.get().max(deadline) + delay);
+    }
+}
+
+#[test]
+fn test_scheduler() {
+    const PERIOD: Duration = Duration::from_millis(1);
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let mut node_cyc = accept_into_cyclic(init_node(node_thread));
+        while let Ok(request) = node_cyc.read() {
+            let value = node_cyc.get_cyclic_data(&request, 1)[0];
+            let _ = node_cyc.write_cyclic(&[value + 1]);
+        }
+    });
+
+    let Ok(mut mcb_main_cyc) = init_state_change_main(main_thread).into_cyclic() else {
+        panic!("Something wrong");
+    };
+
+    let clock = Rc::new(Cell::new(Duration::from_millis(10)));
+    let timer = FakeTimer {
+        clock: clock.clone(),
+        wake_delays: vec![Duration::ZERO, Duration::from_micros(100)],
+        deadlines: Vec::new(),
+    };
+    let mut scheduler = create_scheduler(PERIOD, timer);
+    let mut output = [1u16];
+
+    scheduler
+        .cycle(
+            &mut mcb_main_cyc,
+            NODE_SUBNODE,
+            &mut output,
+            |input, output| output.copy_from_slice(input),
+        )
+        .unwrap();
+    assert_eq!(output, [2]);
+
+    scheduler
+        .cycle(
+            &mut mcb_main_cyc,
+            NODE_SUBNODE,
+            &mut output,
+            |input, output| {
+                output.copy_from_slice(input);
+                clock.set(clock.get() + Duration::from_micros(2500));
+            },
+        )
+        .unwrap();
+
+    let stats = scheduler.statistics();
+    assert_eq!(stats.overruns, 1);
+    assert_eq!(stats.missed_deadlines, 2);
+    assert_eq!(stats.jitter.max, Some(Duration::from_micros(100)));
+
+    let mut remaining = 2;
+    scheduler
+        .run(
+            &mut mcb_main_cyc,
+            NODE_SUBNODE,
+            &mut output,
+            |input, output| {
+                output.copy_from_slice(input);
+                remaining -= 1;
+                remaining > 0
+            },
+        )
+        .unwrap();
+    assert_eq!(output, [5]);
+
+    let stats = scheduler.statistics();
+    assert_eq!(stats.cycles, 4);
+    assert_eq!(stats.errors, 0);
+    assert_eq!(stats.overruns, 1);
+    assert_eq!(stats.jitter.count, 4);
+    assert_eq!(stats.jitter.min, Some(Duration::ZERO));
+
+    scheduler.reset_statistics();
+    assert_eq!(scheduler.statistics(), CycleStatistics::default());
+
+    let start = Duration::from_millis(10);
+    assert_eq!(
+        scheduler.into_timer().deadlines,
+        [
+            start,
+            start + PERIOD,
+            start + PERIOD * 4,
+            start + PERIOD * 5
+        ]
+    );
+}
+
+fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+#[test]
+fn test_shared_main() {
+    const ADDRESS: u16 = 0x011u16;
+    const ERROR_CODE: u32 = 0x0601_0000u32;
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (written_tx, written_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut node_cfg = init_node(node_thread);
+        let request = node_cfg.read().unwrap();
+        let _ = node_cfg.write_u16(request.address, 0x0101);
+
+        let mut node_cyc = accept_into_cyclic(node_cfg);
+        while let Ok(request) = node_cyc.read() {
+            let _ = match request.command {
+                CommandType::Read if request.address == ADDRESS => {
+                    node_cyc.answer_config(request.address, &[0x1234])
+                }
+                CommandType::Read => node_cyc.reject_config(ERROR_CODE),
+                CommandType::Write => {
+                    written_tx
+                        .send(node_cyc.get_data_words(&request).to_vec())
+                        .unwrap();
+                    node_cyc.answer_config(request.address, &[])
+                }
+                _ => Ok(Success),
+            };
+            let data = node_cyc.get_cyclic_data(&request, 1).to_vec();
+            let _ = node_cyc.write_cyclic(&data);
+        }
+    });
+
+    let shared = create_shared_main(init_state_change_main(main_thread));
+    assert_send_sync(&shared);
+    assert!(matches!(
+        shared.cycle(NODE_SUBNODE, &[0x0A0B]),
+        Err(IntfError::InvalidParameter)
+    ));
+    assert_eq!(
+        shared
+            .read(NODE_SUBNODE, ADDRESS, DataType::U16)
+            .recv()
+            .unwrap()
+            .unwrap(),
+        Value::U16(0x0101)
+    );
+
+    shared.into_cyclic().unwrap();
+    assert!(shared.is_cyclic());
+
+    let ui = shared.clone();
+    let (read, write, rejected, segmented) = thread::spawn(move || {
+        (
+            ui.read(NODE_SUBNODE, ADDRESS, DataType::U16),
+            ui.write(NODE_SUBNODE, ADDRESS, Value::U32(0x0001_0002)),
+            ui.read(NODE_SUBNODE, 0x020, DataType::U16),
+            ui.write(NODE_SUBNODE, ADDRESS, Value::Str("Segmented".to_string())),
+        )
+    })
+    .join()
+    .unwrap();
+    assert_eq!(shared.pending(), 4);
+    assert!(read.try_recv().is_err());
+
+    for _ in 0..5 {
+        assert_eq!(shared.cycle(NODE_SUBNODE, &[0x0A0B]).unwrap(), [0x0A0B]);
+    }
+    assert_eq!(shared.pending(), 0);
+    assert_eq!(read.recv().unwrap().unwrap(), Value::U16(0x1234));
+    assert!(matches!(write.recv().unwrap(), Ok(Success)));
+    assert_eq!(written_rx.recv().unwrap(), [0x0002, 0x0001, 0, 0]);
+    assert!(matches!(
+        rejected.recv().unwrap(),
+        Err(IntfError::Access(ERROR_CODE))
+    ));
+    assert!(matches!(segmented.recv().unwrap(), Ok(Success)));
+    assert_eq!(
+        decode_str(&written_rx.recv().unwrap()),
+        "Segmented".to_string()
+    );
+}
+
+#[test]
+fn test_cyclic_config_access() {
+    const ERROR_CODE: u32 = 0x0601_0000u32;
+    const NAME: &str = "Everest CORE drive";
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (cyclic_tx, cyclic_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut registers: HashMap<u16, Vec<u16>> = HashMap::new();
+        let mut node_cyc = accept_into_cyclic(init_node(node_thread));
+        while let Ok(request) = node_cyc.read() {
+            let _ = match request.command {
+                CommandType::Write => {
+                    let words = node_cyc.get_data_words(&request).to_vec();
+                    registers.insert(request.address, words);
+                    node_cyc.answer_config(request.address, &[])
+                }
+                CommandType::Read => match registers.get(&request.address) {
+                    Some(words) => node_cyc.answer_config(request.address, words),
+                    None => node_cyc.reject_config(ERROR_CODE),
+                },
+                _ => Ok(Success),
+            };
+            let data = node_cyc.get_cyclic_data(&request, 2).to_vec();
+            cyclic_tx.send(data.clone()).unwrap();
+            let _ = node_cyc.write_cyclic(&data);
+        }
+    });
+
+    let Ok(mut mcb_main_cyc) = init_state_change_main(main_thread).into_cyclic() else {
+        panic!("Something wrong");
+    };
+    assert_eq!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102, 0x0304]).unwrap(),
+        [0x0102, 0x0304]
+    );
+
+    assert!(mcb_main_cyc
+        .write_u32(NODE_SUBNODE, 0x010, 0x1234_5678)
+        .is_ok());
+    assert!(matches!(
+        mcb_main_cyc.read_u32(NODE_SUBNODE, 0x010),
+        Ok(0x1234_5678u32)
+    ));
+    assert!(mcb_main_cyc.write_i8(NODE_SUBNODE, 0x011, -5).is_ok());
+    assert!(matches!(mcb_main_cyc.read_i8(NODE_SUBNODE, 0x011), Ok(-5)));
+    assert!(mcb_main_cyc
+        .write_u64(NODE_SUBNODE, 0x012, u64::MAX - 1)
+        .is_ok());
+    assert!(matches!(
+        mcb_main_cyc.read_u64(NODE_SUBNODE, 0x012),
+        Ok(value) if value == u64::MAX - 1
+    ));
+    assert!(mcb_main_cyc.write_f32(NODE_SUBNODE, 0x013, 1.5).is_ok());
+    assert_float_eq!(
+        mcb_main_cyc.read_f32(NODE_SUBNODE, 0x013).unwrap(),
+        1.5,
+        abs <= 0.000_1
+    );
+    assert!(matches!(
+        mcb_main_cyc.read_words(NODE_SUBNODE, 0x013),
+        Ok(words) if words == [0x0000, 0x3FC0, 0, 0]
+    ));
+    assert!(mcb_main_cyc.write_f64(NODE_SUBNODE, 0x015, -2.25).is_ok());
+    assert_float_eq!(
+        mcb_main_cyc.read_f64(NODE_SUBNODE, 0x015).unwrap(),
+        -2.25,
+        abs <= 0.000_1
+    );
+
+    assert!(mcb_main_cyc.write_str(NODE_SUBNODE, 0x014, NAME).is_ok());
+    assert_eq!(mcb_main_cyc.read_str(NODE_SUBNODE, 0x014).unwrap(), NAME);
+    assert!(matches!(
+        mcb_main_cyc.read_value(NODE_SUBNODE, 0x014, DataType::Str),
+        Ok(Value::Str(name)) if name == NAME
+    ));
+    assert!(matches!(
+        mcb_main_cyc.read_u16(NODE_SUBNODE, 0x020),
+        Err(IntfError::Access(ERROR_CODE))
+    ));
+    assert!(matches!(
+        mcb_main_cyc.read_u16(NODE_SUBNODE, 0x1000),
+        Err(IntfError::AddressOutOfIndex)
+    ));
+    assert!(!mcb_main_cyc.has_pending_access());
+
+    let frames: Vec<Vec<u16>> = cyclic_rx.try_iter().collect();
+    assert_eq!(frames.len(), 1 + 8 + 3 + 6 + 3 + 1);
+    assert!(frames.iter().all(|data| data == &[0x0102, 0x0304]));
+}
+
+/// Main interface corrupting the CRC of the answers with the given numbers,
+/// counted from one
+struct CorruptAnswers {
+    main_thread: MainThread<[u16; MAX_FRAME_SIZE]>,
+    answers: Vec<usize>,
+    received: usize,
+}
+
+impl PhysicalInterface for CorruptAnswers {
+    fn raw_write(&mut self, frame: &[u16]) -> Result<IntfResult, IntfError> {
+        self.main_thread.raw_write(frame)
+    }
+
+    fn raw_read(&mut self) -> Result<IntfResult, IntfError> {
+        let result = self.main_thread.raw_read();
+        self.received += 1;
+        match result {
+            Ok(Data(mut data)) if self.answers.contains(&self.received) => {
+                data[6] ^= 0xFFFF;
+                Ok(Data(data))
+            }
+            result => result,
+        }
+    }
+
+    fn is_data2read(&mut self) -> Result<IntfResult, IntfError> {
+        self.main_thread.is_data2read()
+    }
+}
+
+#[test]
+fn test_cyclic_access_corrupted_segment() {
+    const ADDRESS: u16 = 0x010u16;
+    let words: Vec<u16> = (1..=10).collect();
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (written_tx, written_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut registers: HashMap<u16, Vec<u16>> = HashMap::new();
+        let mut node_cyc = accept_into_cyclic(init_node(node_thread));
+        while let Ok(request) = node_cyc.read() {
+            let _ = match request.command {
+                CommandType::Write => {
+                    let words = node_cyc.get_data_words(&request).to_vec();
+                    written_tx.send(words.clone()).unwrap();
+                    registers.insert(request.address, words);
+                    node_cyc.answer_config(request.address, &[])
+                }
+                CommandType::Read => {
+                    node_cyc.answer_config(request.address, &registers[&request.address])
+                }
+                _ => Ok(Success),
+            };
+            let data = node_cyc.get_cyclic_data(&request, 1).to_vec();
+            let _ = node_cyc.write_cyclic(&data);
+        }
+    });
+
+    // Answers: state change, cycle, write segments 1 and 2, then the idle
+    // frame and the three segments of the repeated write, read segments 1
+    // and 2, then the idle frame and the three segments of the repeated read
+    let interface = CorruptAnswers {
+        main_thread,
+        answers: vec![4, 10],
+        received: 0,
+    };
+    let mcb_main_test: Main<Init, CorruptAnswers> =
+        create_main_mcb(Some(interface), ExtMode::Extended, NODE_SUBNODE);
+    let Ok(mut mcb_main_cyc) = mcb_main_test.init().into_cyclic() else {
+        panic!("Something wrong");
+    };
+    assert!(mcb_main_cyc.cycle(NODE_SUBNODE, &[0x0102]).is_ok());
+
+    assert!(matches!(
+        mcb_main_cyc.write_words(NODE_SUBNODE, ADDRESS, &words),
+        Err(IntfError::Crc)
+    ));
+    assert!(!mcb_main_cyc.has_pending_access());
+    assert!(mcb_main_cyc
+        .write_words(NODE_SUBNODE, ADDRESS, &words)
+        .is_ok());
+
+    // The segments taken before the failure are dropped by the node
+    let written: Vec<Vec<u16>> = written_rx.try_iter().collect();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0][..10], words[..]);
+    assert_eq!(written[0][10..], [0, 0]);
+
+    assert!(matches!(
+        mcb_main_cyc.read_words(NODE_SUBNODE, ADDRESS),
+        Err(IntfError::Crc)
+    ));
+    assert_eq!(
+        mcb_main_cyc.read_words(NODE_SUBNODE, ADDRESS).unwrap(),
+        written[0]
+    );
+}
+
+#[test]
+fn test_cyclic_access_idle_answer() {
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        // The node ignores the config words and answers with idle frames
+        let mut node_cyc = accept_into_cyclic(init_node(node_thread));
+        while let Ok(request) = node_cyc.read() {
+            let data = node_cyc.get_cyclic_data(&request, 1).to_vec();
+            let _ = node_cyc.write_cyclic(&data);
+        }
+    });
+
+    let Ok(mut mcb_main_cyc) = init_state_change_main(main_thread).into_cyclic() else {
+        panic!("Something wrong");
+    };
+    assert!(matches!(
+        mcb_main_cyc.read_u16(NODE_SUBNODE, 0x010u16),
+        Err(IntfError::WrongCommand)
+    ));
+
+    let stats = mcb_main_cyc.statistics();
+    assert_eq!(stats.wrong_commands, 1);
+    assert!(stats.access_errors.is_empty());
+}
+
+#[cfg(feature = "log")]
+struct CaptureLogger(std::sync::Mutex<Vec<String>>);
+
+#[cfg(feature = "log")]
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target().starts_with("mcb")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn test_log_transactions() {
+    static LOGGER: CaptureLogger = CaptureLogger(std::sync::Mutex::new(Vec::new()));
+    const ADDRESS: u16 = 0x011u16;
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        serve_requests(init_node(node_thread), |node_cfg, request| {
+            let _ = node_cfg.write_u16(request.address, 0x1234);
+        });
+    });
+
+    let mut mcb_main_cfg = init_main(main_thread);
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Ok(0x1234u16)
+    ));
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, 0x1000),
+        Err(IntfError::AddressOutOfIndex)
+    ));
+
+    let lines = LOGGER.0.lock().unwrap();
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("access subnode 2 address 0x011 command 0x2: ok in")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("read subnode 2 address 0x011 command 0x2: ok in")));
+    assert!(lines
+        .iter()
+        .any(|line| line.contains("address 0x1000 command 0x2: AddressOutOfIndex in")));
+}
+
+#[test]
+fn test_sized_frames() {
+    const ADDRESS: u16 = 0x020u16;
+    const FRAME_SIZE: usize = 16;
+    let words: Vec<u16> = (1..=9).collect();
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (request_tx, request_rx) = mpsc::channel();
+
+    let node_words = words.clone();
+    thread::spawn(move || {
+        let mcb_node_test: Node<Init, NodeThread<[u16; FRAME_SIZE]>, FRAME_SIZE> =
+            create_sized_node_mcb(Some(node_thread), ExtMode::Extended, NODE_SUBNODE);
+        let mut node_cfg = mcb_node_test.init();
+
+        let request = node_cfg.read().unwrap();
+        request_tx
+            .send(node_cfg.get_data_words(&request).to_vec())
+            .unwrap();
+        let _ = node_cfg.ack(request.address);
+
+        // Answers not fitting in the frame are not sent
+        let request = node_cfg.read().unwrap();
+        request_tx
+            .send(match node_cfg.write_words(request.address, &[0u16; 10]) {
+                Err(IntfError::InvalidParameter) => vec![],
+                _ => vec![0xFFFF],
+            })
+            .unwrap();
+        let _ = node_cfg.write_words(request.address, &node_words);
+
+        let request = node_cfg.read().unwrap();
+        if !matches!(
+            request.command,
+            CommandType::StateChange(TargetState::Cyclic)
+        ) {
+            panic!("Something wrong");
+        }
+        let Ok(mut node_cyc) = node_cfg.into_cyclic(&request) else {
+            panic!("Something wrong");
+        };
+
+        let request = node_cyc.read().unwrap();
+        let inputs: Vec<u16> = node_cyc
+            .get_cyclic_data(&request, FRAME_SIZE)
+            .iter()
+            .map(|word| word + 1)
+            .collect();
+        let _ = node_cyc.write_cyclic(&inputs);
+    });
+
+    let mcb_main_test: Main<Init, MainThread<[u16; FRAME_SIZE]>, FRAME_SIZE> =
+        create_sized_main_mcb(Some(main_thread), ExtMode::Extended, NODE_SUBNODE);
+    let mut mcb_main_cfg = mcb_main_test.init();
+    assert_eq!(mcb_main_cfg.frame_size(), FRAME_SIZE);
+    assert_eq!(mcb_main_cfg.max_extended_words(), 9);
+    assert_eq!(mcb_main_cfg.max_cyclic_words(), 9);
+
+    // Payloads not fitting in the frame are rejected before sending
+    assert!(matches!(
+        mcb_main_cfg.write_words(NODE_SUBNODE, ADDRESS, &[0u16; 10]),
+        Err(IntfError::InvalidParameter)
+    ));
+    assert!(matches!(
+        mcb_main_cfg.write_str(NODE_SUBNODE, ADDRESS, "longer than a frame"),
+        Err(IntfError::InvalidParameter)
+    ));
+    mcb_main_cfg.set_payload_crc(true);
+    assert_eq!(mcb_main_cfg.max_extended_words(), 8);
+    assert!(matches!(
+        mcb_main_cfg.write_words(NODE_SUBNODE, ADDRESS, &words),
+        Err(IntfError::InvalidParameter)
+    ));
+    mcb_main_cfg.set_payload_crc(false);
+    assert_eq!(mcb_main_cfg.statistics().frames_sent, 0);
+
+    assert!(mcb_main_cfg
+        .write_words(NODE_SUBNODE, ADDRESS, &words)
+        .is_ok());
+    assert_eq!(request_rx.recv().unwrap(), words);
+    assert_eq!(
+        mcb_main_cfg.read_words(NODE_SUBNODE, ADDRESS).unwrap(),
+        words
+    );
+    assert!(request_rx.recv().unwrap().is_empty());
+
+    let Ok(mut mcb_main_cyc) = mcb_main_cfg.into_cyclic() else {
+        panic!("Something wrong");
+    };
+    assert!(matches!(
+        mcb_main_cyc.cycle(NODE_SUBNODE, &[0u16; 10]),
+        Err(IntfError::InvalidParameter)
+    ));
+    let inputs: Vec<u16> = (2..=10).collect();
+    assert_eq!(mcb_main_cyc.cycle(NODE_SUBNODE, &words).unwrap(), inputs);
+}
+
+#[test]
+fn test_sized_node_write_str() {
+    const ADDRESS: u16 = 0x020u16;
+    const FRAME_SIZE: usize = 16;
+    const DATA: &str = "sized frame";
+    let (node_thread, main_thread) = create_mainnodethread();
+    let (result_tx, result_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mcb_node_test: Node<Init, NodeThread<[u16; FRAME_SIZE]>, FRAME_SIZE> =
+            create_sized_node_mcb(Some(node_thread), ExtMode::Extended, NODE_SUBNODE);
+        let mut node_cfg = mcb_node_test.init();
+
+        let request = node_cfg.read().unwrap();
+        result_tx
+            .send(node_cfg.write_str(request.address, "longer than a frame"))
+            .unwrap();
+        let _ = node_cfg.write_str(request.address, DATA);
+    });
+
+    let mcb_main_test: Main<Init, MainThread<[u16; FRAME_SIZE]>, FRAME_SIZE> =
+        create_sized_main_mcb(Some(main_thread), ExtMode::Extended, NODE_SUBNODE);
+    let mut mcb_main_cfg = mcb_main_test.init();
+
+    assert_eq!(mcb_main_cfg.read_str(NODE_SUBNODE, ADDRESS).unwrap(), DATA);
+    assert!(matches!(
+        result_rx.recv().unwrap(),
+        Err(IntfError::InvalidParameter)
+    ));
+}
+
+#[test]
+fn test_minimal_frame_size() {
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    let mcb_main_test: Main<Init, MainThread<[u16; 7]>, 7> =
+        create_sized_main_mcb(Some(main_thread), ExtMode::Extended, NODE_SUBNODE);
+    let mut mcb_main_cfg = mcb_main_test.init();
+    mcb_main_cfg.set_payload_crc(true);
+    assert_eq!(mcb_main_cfg.max_extended_words(), 0);
+
+    let mcb_node_test: Node<Init, NodeThread<[u16; 7]>, 7> =
+        create_sized_node_mcb(Some(node_thread), ExtMode::Extended, NODE_SUBNODE);
+    let mut node_cfg = mcb_node_test.init();
+    node_cfg.set_payload_crc(true);
+    assert_eq!(node_cfg.max_extended_words(), 0);
+}
+
+#[test]
+fn test_oversized_frame() {
+    const ADDRESS: u16 = 0x011u16;
+    let (node_thread, main_thread) = create_mainnodethread();
+
+    thread::spawn(move || {
+        let ack = (ADDRESS << 4) | 0x6;
+        if node_thread.rx_channel.recv().is_ok() {
+            let answer = sealed_frame([NODE_SUBNODE as u16, ack, 0x1234, 0, 0, 0]);
+            node_thread.tx_channel.send(answer).unwrap();
+        }
+    });
+
+    let mcb_main_test: Main<Init, MainThread<[u16; MAX_FRAME_SIZE]>, 16> =
+        create_sized_main_mcb(Some(main_thread), ExtMode::Extended, NODE_SUBNODE);
+    let mut mcb_main_cfg = mcb_main_test.init();
+
+    assert!(matches!(
+        mcb_main_cfg.read_u16(NODE_SUBNODE, ADDRESS),
+        Err(IntfError::InvalidParameter)
+    ));
+    assert_eq!(mcb_main_cfg.statistics().oversized_frames, 1);
+}